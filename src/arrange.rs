@@ -1,33 +1,193 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
 use regex::Regex;
 
-pub fn arrange_text_string(s: &mut String) {
-    // many spaces => single space
-    let re = Regex::new(r"[ \t]+").unwrap();
-    *s = re.replace_all(s, " ").to_string();
-    
-    // delete space at the start of line
-    *s = s.replace("\n ", "\n");
-    
-    // more than three `\n` => `\n\n`
-    let re = Regex::new(r"\n\n\n+").unwrap();
-    *s = re.replace_all(s, "\n\n").to_string();
-    
-    // delete spaces before
-    // `,`
-    // `.`
-    // `]`
-    *s = s.replace(" ,", ",");
-    *s = s.replace(" .",  ".");
-    *s = s.replace(" ]",  "]");
-
-    // delete spaces after '['
-    *s = s.replace("[ ",  "[");
-
-    // delete space before and after `'` and `"`
-    let re = Regex::new(" ?['\"] ?").unwrap();
-    *s = re.replace_all(s, "").to_string();
-
-    // replace `` with "
-    *s = s.replace("``",  "\"");
-    *s = s.replace("''",  "\"");
+use crate::ast::{Ast, Command, Paragraph, Text, Word};
+
+/// A normalization rule for the text runs of an [`Ast`].
+/// The rules change the text and the spacing between words, but not the positions of the text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rule {
+    /// Collapses the white spaces in a text run, and drops empty runs.
+    Whitespace,
+    /// Attaches punctuation such as `,` and `.` to the previous word.
+    Punctuation,
+    /// Attaches words to the brackets around them, as in `(see [1])`.
+    Brackets,
+    /// Writes the quotes of TeX such as ``` ``a'' ``` and apostrophes as typographic quotes
+    /// `“a”` and `’`, instead of `"a"` and `'`.
+    SmartQuotes,
+    /// Writes en and em dashes as `-` and `--`.
+    AsciiDashes,
+}
+
+impl FromStr for Rule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rule = match s {
+            "whitespace" => Rule::Whitespace,
+            "punctuation" => Rule::Punctuation,
+            "brackets" => Rule::Brackets,
+            "smart-quotes" => Rule::SmartQuotes,
+            "ascii-dashes" => Rule::AsciiDashes,
+            _ => return Err(format!("unknown rule: {s}")),
+        };
+        Ok(rule)
+    }
+}
+
+/// The rules applied by [`normalize`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Rules(Vec<Rule>);
+
+impl Default for Rules {
+    fn default() -> Self {
+        use Rule::*;
+        Rules(vec![Whitespace, Punctuation, Brackets])
+    }
+}
+
+impl Rules {
+    pub fn enable(&mut self, rule: Rule) {
+        if !self.has(rule) {
+            self.0.push(rule);
+        }
+    }
+
+    pub fn disable(&mut self, rule: Rule) {
+        self.0.retain(|r| *r != rule);
+    }
+
+    pub fn has(&self, rule: Rule) -> bool {
+        self.0.contains(&rule)
+    }
+}
+
+/// Normalizes the text runs of `ast` in place.
+pub fn normalize(ast: &mut Ast, rules: &Rules) {
+    for paragraph in ast.paragraphs_mut() {
+        normalize_paragraph(paragraph, rules);
+    }
+}
+
+fn normalize_paragraph(paragraph: &mut Paragraph, rules: &Rules) {
+    let words = paragraph.words_mut();
+    for word in words.iter_mut() {
+        match word {
+            Word::Text(text) => normalize_text(text, rules),
+            Word::Lines(ast)
+            | Word::Env(_, ast)
+            | Word::Command(Command::Section(_, ast) | Command::Authors(ast)) => {
+                normalize(ast, rules)
+            }
+            Word::Theorem(theorem) => {
+                if let Some(title) = &mut theorem.title {
+                    normalize(title, rules);
+                }
+                normalize(&mut theorem.body, rules);
+            }
+            Word::Float(float) => {
+                for caption in &mut float.captions {
+                    normalize(&mut caption.text, rules);
+                }
+                for cell in float
+                    .tables
+                    .iter_mut()
+                    .flat_map(|table| &mut table.rows)
+                    .flatten()
+                {
+                    normalize(cell, rules);
+                }
+            }
+            Word::Command(Command::Caption(caption)) => normalize(&mut caption.text, rules),
+            Word::Command(Command::Footnote(footnote)) => normalize(&mut footnote.text, rules),
+            Word::Table(table) => {
+                for cell in table.rows.iter_mut().flatten() {
+                    normalize(cell, rules);
+                }
+            }
+            Word::List(list) => {
+                for item in &mut list.items {
+                    normalize(&mut item.label, rules);
+                    normalize(&mut item.body, rules);
+                }
+            }
+            _ => {}
+        }
+    }
+    if rules.has(Rule::Whitespace) {
+        words.retain(|word| !matches!(word, Word::Text(text) if text.s.is_empty()));
+    }
+    for word in words.iter_mut().skip(1) {
+        let Word::Text(text) = word else {
+            continue;
+        };
+        let punctuation =
+            rules.has(Rule::Punctuation) && text.s.starts_with([',', '.', ';', ':', '!', '?']);
+        let closing = rules.has(Rule::Brackets) && text.s.starts_with([')', ']', '”', '’']);
+        text.glued |= punctuation || closing;
+    }
+    if rules.has(Rule::Brackets) {
+        for word in words.iter_mut() {
+            if let Word::Text(text) = word {
+                text.joins_next |= text.s.ends_with(['(', '[', '“', '‘']);
+            }
+        }
+    }
+}
+
+fn normalize_text(text: &mut Text, rules: &Rules) {
+    if rules.has(Rule::Whitespace) {
+        text.s = text.s.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    text.s = match rules.has(Rule::SmartQuotes) {
+        true => text
+            .s
+            .replace("``", "“")
+            .replace("''", "”")
+            .replace('`', "‘")
+            .replace('\'', "’"),
+        false => text
+            .s
+            .replace("``", "\"")
+            .replace("''", "\"")
+            .replace('`', "'"),
+    };
+    if rules.has(Rule::AsciiDashes) {
+        text.s = text.s.replace('–', "-").replace('—', "--");
+    }
+}
+
+/// Tidies the layout of the rendered text: single spaces, no spaces around line breaks,
+/// and at most one blank line between paragraphs.
+/// An indentation of two spaces or more, as of nested list items, is kept.
+pub fn tidy(s: &mut String) {
+    static SPACES: OnceLock<Regex> = OnceLock::new();
+    static LINE_START: OnceLock<Regex> = OnceLock::new();
+    static LINE_END: OnceLock<Regex> = OnceLock::new();
+    static BLANK_LINES: OnceLock<Regex> = OnceLock::new();
+    let spaces = SPACES.get_or_init(|| Regex::new(r"(\S)[ \t]+").unwrap());
+    let line_start = LINE_START.get_or_init(|| Regex::new(r"(?m)^ (\S)").unwrap());
+    let line_end = LINE_END.get_or_init(|| Regex::new(r"(?m)[ \t]+$").unwrap());
+    let blank_lines = BLANK_LINES.get_or_init(|| Regex::new(r"\n\n\n+").unwrap());
+    *s = spaces.replace_all(s, "$1 ").to_string();
+    *s = line_start.replace_all(s, "$1").to_string();
+    *s = line_end.replace_all(s, "").to_string();
+    *s = blank_lines.replace_all(s, "\n\n").to_string();
+}
+
+#[test]
+fn test_normalize() {
+    let doc = crate::token::Document::from_str(r"a  ``b'' ( c ) \cite{x}, d [e] f--g.").unwrap();
+    let mut ast = crate::ast::convert(None, doc, Default::default());
+    normalize(&mut ast, &Rules::default());
+    assert_eq!(format!("{ast}"), "a \"b\" (c) [XX], d [e] f–g.");
+    let mut rules = Rules::default();
+    rules.disable(Rule::Brackets);
+    rules.enable(Rule::AsciiDashes);
+    let doc = crate::token::Document::from_str(r"( c ) f--g").unwrap();
+    let mut ast = crate::ast::convert(None, doc, Default::default());
+    normalize(&mut ast, &rules);
+    assert_eq!(format!("{ast}"), "( c ) f-g");
 }
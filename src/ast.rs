@@ -1,14 +1,65 @@
+mod beamer;
+mod cite;
 mod command;
+mod context;
+mod counter;
+mod document;
 mod env;
+mod float;
+mod footnote;
+mod front;
+mod label;
+mod list;
+mod reference;
+mod resolver;
+mod sentence;
+mod table;
+mod theorem;
 mod token_to_ast;
 use std::fmt;
 
+use crate::token;
+pub use cite::{Cite, Work};
 pub use command::Command;
+pub use context::Context;
+pub use list::{Item, List, ListKind};
+pub use document::{Block, Document, Section};
+pub use float::{Caption, Float};
+pub use footnote::{place_footnotes, Footnote, Placement};
+pub use front::drop_authors;
+pub use resolver::Resolver;
+pub use sentence::Sentence;
+pub use table::{drop_tables, Table};
+pub use theorem::Theorem;
 pub use token_to_ast::token_to_ast;
+pub use crate::token::Position;
 
 use self::env::write_env;
 
-#[derive(Debug, PartialEq, Eq)]
+/// Converts a document in two passes like LaTeX does:
+/// the first pass collects the labels which the references of the second one use.
+pub fn convert(
+    preamble: Option<token::Document>,
+    mut body: token::Document,
+    resolver: Resolver,
+) -> Ast {
+    let mut ctx = Context::new(resolver);
+    if let Some(preamble) = preamble.clone() {
+        token_to_ast(preamble, &mut ctx);
+    }
+    if ctx.is_beamer() {
+        beamer::strip_overlays(&mut body);
+    }
+    token_to_ast(body.clone(), &mut ctx);
+
+    let mut ctx = ctx.next_pass();
+    if let Some(preamble) = preamble {
+        token_to_ast(preamble, &mut ctx);
+    }
+    token_to_ast(body, &mut ctx)
+}
+
+#[derive(Debug, PartialEq, Eq, Default)]
 pub struct Ast(Vec<Paragraph>);
 
 impl Ast {
@@ -18,6 +69,17 @@ impl Ast {
     pub fn push(&mut self, paragraph: Paragraph) {
         self.0.push(paragraph);
     }
+    pub fn paragraphs(&self) -> &[Paragraph] {
+        &self.0
+    }
+    pub fn paragraphs_mut(&mut self) -> &mut [Paragraph] {
+        &mut self.0
+    }
+    /// The paragraphs with a sentence on each line, prefixed by `file:line` if `prefix` is given,
+    /// or by `line` if it is `Some(None)`.
+    pub fn sentence_lines(&self, prefix: Option<Option<&str>>) -> Vec<String> {
+        sentence::lines(self, prefix)
+    }
 }
 
 impl fmt::Display for Ast {
@@ -33,7 +95,7 @@ impl fmt::Display for Ast {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Default)]
 pub struct Paragraph(Vec<Word>);
 
 impl Paragraph {
@@ -43,35 +105,74 @@ impl Paragraph {
     pub fn push(&mut self, word: Word) {
         self.0.push(word);
     }
+    pub fn words(&self) -> &[Word] {
+        &self.0
+    }
+    pub fn words_mut(&mut self) -> &mut Vec<Word> {
+        &mut self.0
+    }
+    pub fn sentences(&self) -> Vec<Sentence<'_>> {
+        sentence::split(&self.0)
+    }
 }
 
 impl fmt::Display for Paragraph {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut iter = self.0.iter().peekable();
-        while let Some(w) = iter.next() {
-            write!(f, "{w}")?;
-            if iter.peek().is_some() {
-                write!(f, " ")?;
-            }
+        write_words(f, &self.0)
+    }
+}
+
+/// Writes words separated by spaces, except around glued text.
+fn write_words(f: &mut fmt::Formatter<'_>, words: &[Word]) -> fmt::Result {
+    let mut iter = words.iter().peekable();
+    while let Some(w) = iter.next() {
+        write!(f, "{w}")?;
+        match (w, iter.peek()) {
+            (_, None) => {}
+            (Word::Text(text), _) if text.joins_next => {}
+            (_, Some(Word::Text(text))) if text.glued => {}
+            _ => write!(f, " ")?,
         }
-        Ok(())
     }
+    Ok(())
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Word {
-    Text(String),
+    Text(Text),
     Env(String, Ast),
+    /// A theorem-like environment or a proof.
+    Theorem(Theorem),
+    List(List),
+    /// A figure or a table.
+    Float(Float),
+    /// The cells of a `tabular`.
+    Table(Table),
     MathInline(String),
     Command(command::Command),
     Lines(Ast),
 }
 
+impl Word {
+    /// Whether the word is inline or display math.
+    pub fn is_math(&self) -> bool {
+        match self {
+            Word::MathInline(_) => true,
+            Word::Env(env, _) => env::is_math(env),
+            _ => false,
+        }
+    }
+}
+
 impl fmt::Display for Word {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Word::Text(s) => write!(f, "{}", s),
+            Word::Text(text) => write!(f, "{text}"),
             Word::Env(env, ast) => write_env(f, env, ast),
+            Word::Theorem(theorem) => writeln!(f, "\n{theorem}"),
+            Word::List(list) => write!(f, "{list}"),
+            Word::Float(float) => writeln!(f, "\n{float}"),
+            Word::Table(table) => writeln!(f, "\n{table}"),
             Word::MathInline(s) => write!(f, "{s}"),
             Word::Command(c) => write!(f, "{c}"),
             Word::Lines(ast) => write!(f, "{ast}"),
@@ -79,6 +180,42 @@ impl fmt::Display for Word {
     }
 }
 
+/// A run of text such as a word.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Text {
+    pub s: String,
+    /// Where the text starts in the source, unless the conversion made it up.
+    pub position: Option<Position>,
+    /// Whether the text is attached to the previous word without a space.
+    pub glued: bool,
+    /// Whether the next word is attached to the text without a space.
+    pub joins_next: bool,
+}
+
+impl Text {
+    pub fn new(s: impl Into<String>) -> Self {
+        Text {
+            s: s.into(),
+            position: None,
+            glued: false,
+            joins_next: false,
+        }
+    }
+
+    pub fn at(s: impl Into<String>, position: Position) -> Self {
+        Text {
+            position: Some(position),
+            ..Text::new(s)
+        }
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.s.replace(token::NON_FINAL, ""))
+    }
+}
+
 pub fn make_upper_substitute(s: String) -> String {
     let mut s = take_alph_and_to_upper(s);
     if s.len() < 2 {
@@ -0,0 +1,242 @@
+use super::command::Command;
+use super::token_to_ast::{take_group, take_optional, token_to_ast};
+use super::{Ast, Context, Paragraph, Theorem, Word};
+use crate::token;
+
+/// Blocks with a title, written as theorems are.
+const BLOCKS: [&str; 3] = ["block", "exampleblock", "alertblock"];
+
+/// Commands and environments which take an overlay specification such as `<2->`.
+const OVERLAYS: [&str; 21] = [
+    "item",
+    "pause",
+    "onslide",
+    "only",
+    "uncover",
+    "visible",
+    "invisible",
+    "alert",
+    "structure",
+    "temporal",
+    "action",
+    "frametitle",
+    "framesubtitle",
+    "frame",
+    "block",
+    "exampleblock",
+    "alertblock",
+    "itemize",
+    "enumerate",
+    "description",
+    "onlyenv",
+];
+
+/// Drops the overlay specifications after the commands and at the start of the environments
+/// which take them, e.g. `\item<2->` and `\begin{frame}<3>`. Only beamer has them,
+/// so the text of `\item <x>` is kept in other classes.
+pub(super) fn strip_overlays(doc: &mut token::Document) {
+    let mut i = 0;
+    while i < doc.words.len() {
+        let takes = match &mut doc.words[i] {
+            token::Word::Command(c) => command_name(c).is_some_and(|name| OVERLAYS.contains(&name)),
+            token::Word::Env(env, body) => {
+                if OVERLAYS.contains(&env.as_str()) {
+                    strip_overlay(body, 0);
+                }
+                strip_overlays(body);
+                false
+            }
+            token::Word::Lines(body) | token::Word::Optional(body) => {
+                strip_overlays(body);
+                false
+            }
+            _ => false,
+        };
+        if takes {
+            strip_overlay(doc, i + 1);
+        }
+        i += 1;
+    }
+}
+
+fn command_name(c: &token::Command) -> Option<&str> {
+    match c {
+        token::Command::Item => Some("item"),
+        token::Command::FrameTitle => Some("frametitle"),
+        token::Command::Frame => Some("frame"),
+        token::Command::Known(sig) => Some(sig.name),
+        token::Command::Unknown(name) => Some(name),
+        _ => None,
+    }
+}
+
+/// Drops an overlay specification starting at the word `i` of `doc`, if there is one.
+/// It is text of one or more words, as `<3| handout:0>`, and may run into the next word.
+fn strip_overlay(doc: &mut token::Document, i: usize) {
+    let Some(token::Word::Text(first, _)) = doc.words.get(i) else {
+        return;
+    };
+    if !first.starts_with('<') {
+        return;
+    }
+    let mut end = i;
+    loop {
+        match doc.words.get_mut(end) {
+            Some(token::Word::Text(s, position)) => match s.find('>') {
+                Some(close) => {
+                    let rest = s.split_off(close + 1);
+                    if rest.is_empty() {
+                        end += 1;
+                    } else {
+                        position.column += s.chars().count();
+                        *s = rest;
+                    }
+                    break;
+                }
+                None => end += 1,
+            },
+            // not an overlay specification
+            _ => return,
+        }
+    }
+    doc.words.drain(i..end);
+}
+
+/// Whether an environment is a frame, whose paragraphs stand in the document as a section.
+pub(super) fn is_frame(env: &str) -> bool {
+    env == "frame"
+}
+
+/// Converts the body of a frame, the environment being entered, into a heading by its title
+/// and the paragraphs after it. A frame without a title has no heading.
+/// Gives the body back for other environments.
+pub(super) fn token_to_frame(
+    env: &str,
+    mut doc: token::Document,
+    ctx: &mut Context,
+) -> Result<Ast, token::Document> {
+    if !is_frame(env) {
+        return Err(doc);
+    }
+    // options such as `fragile`
+    let _ = take_optional(&mut doc);
+    // `\begin{frame}{title}` or `\frametitle{title}` in the body
+    let title = take_group(&mut doc).map(|title| token_to_ast(title, ctx));
+    let body = token_to_ast(doc, ctx);
+    let frame_title = ctx.take_frame_title();
+    let title = title
+        .filter(|title| !title.paragraphs().is_empty())
+        .or(frame_title);
+    let mut paragraphs = Vec::new();
+    if let Some(title) = title {
+        let depth = ctx.frame_depth();
+        paragraphs.push(Paragraph(vec![Word::Command(Command::Section(
+            depth, title,
+        ))]));
+    }
+    paragraphs.extend(body.0);
+    Ok(Ast(paragraphs))
+}
+
+/// Converts the body of a block with a title such as `\begin{block}{Title}`,
+/// the environment being entered. Gives the body back for other environments.
+pub(super) fn token_to_block(
+    env: &str,
+    mut doc: token::Document,
+    ctx: &mut Context,
+) -> Result<Theorem, token::Document> {
+    if !BLOCKS.contains(&env) {
+        return Err(doc);
+    }
+    let title = take_group(&mut doc).map(|title| token_to_ast(title, ctx));
+    let name = title
+        .map(|title| format!("{title}"))
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    // an untitled block is like any other environment
+    if name.is_empty() {
+        return Err(doc);
+    }
+    let body = token_to_ast(doc, ctx);
+    Ok(Theorem {
+        name: name.trim_end_matches('.').to_string(),
+        title: None,
+        body,
+        qed: false,
+    })
+}
+
+#[test]
+fn test_beamer() {
+    use std::str::FromStr;
+    let preamble = r"\documentclass{beamer} \title{Talk}";
+    let s = r"\frame{\titlepage}
+\section{Results}
+\begin{frame}[fragile]{Main result}
+\begin{itemize}
+\item<1-> First \pause
+\item<2-> \alert<2>{Second} \only<3>{and third}
+\end{itemize}
+\end{frame}
+\begin{frame}<2>
+\frametitle<2>{Details}
+\begin{exampleblock}<3>{An example}
+Body.
+\end{exampleblock}
+\end{frame}";
+    let preamble = token::Document::from_str(preamble).unwrap();
+    let doc = token::Document::from_str(s).unwrap();
+    let mut ast = super::convert(Some(preamble), doc, super::Resolver::default());
+    crate::arrange::normalize(&mut ast, &Default::default());
+    let document = super::Document::from(ast);
+    let frames = document.sections[0].sections[0]
+        .sections
+        .iter()
+        .map(|section| section.title_text())
+        .collect::<Vec<_>>();
+    assert_eq!(frames, ["Main result", "Details"]);
+    let lines = format!("{document}")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "# Talk",
+            "## Results",
+            "### Main result",
+            "- First",
+            "- Second and third",
+            "### Details",
+            "An example. Body."
+        ]
+    );
+}
+
+#[test]
+fn test_overlays() {
+    use std::str::FromStr;
+    let s = r"\begin{frame}<2>\item<2->a \only<3| handout:0>{b} $x<y>z$ \end{frame}";
+    let mut doc = token::Document::from_str(s).unwrap();
+    strip_overlays(&mut doc);
+    let token::Word::Env(_, body) = &doc.words[0] else {
+        unreachable!()
+    };
+    assert_eq!(body.words[0], token::Word::Command(token::Command::Item));
+    assert!(matches!(&body.words[1], token::Word::Text(s, _) if s == "a"));
+    assert!(matches!(
+        body.words[2],
+        token::Word::Command(token::Command::Known(_))
+    ));
+    assert!(matches!(body.words[3], token::Word::Lines(_)));
+    assert!(matches!(&body.words[5], token::Word::Text(s, _) if s == "x<y>z"));
+    // other classes have no overlay specifications
+    let s = r"\begin{itemize}\item<text> x\end{itemize}";
+    let doc = token::Document::from_str(s).unwrap();
+    let ast = super::convert(None, doc, super::Resolver::default());
+    assert_eq!(format!("{ast}").trim(), "- <text> x");
+}
@@ -0,0 +1,230 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::reference::join_and;
+use super::{make_upper_substitute, token_to_ast, Context};
+use crate::bib;
+use crate::token::{self, CiteStyle};
+
+/// A citation of one or more works, e.g. `\citet[p.~3]{knuth84,lamport94}`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cite {
+    pub style: CiteStyle,
+    pub works: Vec<Work>,
+    pub prenote: Option<String>,
+    pub postnote: Option<String>,
+}
+
+/// A cited work, with the label, authors and year shown for it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Work {
+    pub key: String,
+    pub label: String,
+    pub author: String,
+    pub year: String,
+}
+
+impl Work {
+    /// Looks up `key` in the `.aux` file and then in the `.bib` files,
+    /// or makes up a label, author and year from the key.
+    pub fn new(key: &str, ctx: &Context) -> Self {
+        let citation = ctx.citation(key);
+        let entry = ctx.bib_entry(key);
+        let author = citation
+            .and_then(|citation| citation.author.clone())
+            .or_else(|| entry.and_then(author_from_entry))
+            .unwrap_or_else(|| author_from_key(key));
+        let year = citation
+            .and_then(|citation| citation.year.clone())
+            .or_else(|| entry.and_then(year_from_entry))
+            .unwrap_or_else(|| year_from_key(key));
+        let label = match citation {
+            // the number of `\bibcite` is not what author-year styles print
+            Some(_) if ctx.is_author_year() => format!("{author}, {year}"),
+            Some(citation) => citation.label.clone(),
+            None => ctx
+                .bib_label(key, &author, &year)
+                .unwrap_or_else(|| make_upper_substitute(key.to_string())),
+        };
+        Work {
+            key: key.to_string(),
+            label,
+            author,
+            year,
+        }
+    }
+}
+
+/// The last names of the authors or editors, e.g. `Knuth`, `Knuth and Lamport` or `Knuth et al.`.
+fn author_from_entry(entry: &bib::Entry) -> Option<String> {
+    let names = &entry
+        .field("author")
+        .or_else(|| entry.field("editor"))?
+        .value;
+    let names = bib::split_names(names);
+    let last = |i: usize| plain(bib::last_name(names[i]));
+    match names.len() {
+        0 => None,
+        1 => Some(last(0)),
+        2 => Some(format!("{} and {}", last(0), last(1))),
+        _ => Some(format!("{} et al.", last(0))),
+    }
+}
+
+/// The year of an entry, or the year of its BibLaTeX date such as `1984-09`.
+fn year_from_entry(entry: &bib::Entry) -> Option<String> {
+    if let Some(year) = entry.field("year") {
+        return Some(plain(&year.value));
+    }
+    let date = &entry.field("date")?.value;
+    Some(date.split(['-', '/']).next()?.trim().to_string())
+}
+
+/// Converts TeX markup in a `.bib` field to plain text.
+fn plain(s: &str) -> String {
+    match token::Document::from_str(s) {
+        Ok(doc) => {
+            let text = format!("{}", token_to_ast(doc, &mut Context::default()));
+            text.trim().to_string()
+        }
+        Err(_) => s.replace(['{', '}'], ""),
+    }
+}
+
+/// Guesses the author from a key such as `smith2020deep` or `DBLP:conf/x/Smith20`.
+fn author_from_key(key: &str) -> String {
+    let key = key.rsplit([':', '/']).next().unwrap_or(key);
+    let mut chars = key.chars().take_while(|c| c.is_alphabetic());
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => make_upper_substitute(key.to_string()),
+    }
+}
+
+/// Guesses the year from a key such as `smith2020deep` or `knuth84`,
+/// or gives `n.d.` as author-year styles do for a work without a date.
+fn year_from_key(key: &str) -> String {
+    let digits = key
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+    match digits.len() {
+        4 => digits,
+        2 if digits.as_str() >= "50" => format!("19{digits}"),
+        2 => format!("20{digits}"),
+        _ => "n.d.".to_string(),
+    }
+}
+
+impl fmt::Display for Cite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prenote = self.prenote.as_ref().map(|note| format!("{note} "));
+        let prenote = prenote.as_deref().unwrap_or_default();
+        let postnote = self.postnote.as_ref().map(|note| format!(", {note}"));
+        let postnote = postnote.as_deref().unwrap_or_default();
+        match self.style {
+            CiteStyle::Plain | CiteStyle::Parenthetical => {
+                let labels = self.works.iter().map(|work| work.label.clone());
+                let labels = labels.collect::<Vec<_>>();
+                // author-year labels such as `Knuth, 1984` are separated by semicolons
+                let separator = match labels.iter().any(|label| label.contains(',')) {
+                    true => "; ",
+                    false => ", ",
+                };
+                let labels = labels.join(separator);
+                write!(f, "[{prenote}{labels}{postnote}]")
+            }
+            // a noun phrase such as `Smith (2020) and Jones (2019)`
+            CiteStyle::Textual => {
+                let mut works = self
+                    .works
+                    .iter()
+                    .map(|work| format!("{} ({})", work.author, work.year))
+                    .collect::<Vec<_>>();
+                if let (Some(last), Some(work)) = (works.last_mut(), self.works.last()) {
+                    *last = format!("{} ({prenote}{}{postnote})", work.author, work.year);
+                }
+                write!(f, "{}", join_and(works))
+            }
+            CiteStyle::Author => {
+                let authors = self.works.iter().map(|work| work.author.clone());
+                write!(f, "{}", join_and(authors.collect()))
+            }
+            CiteStyle::Year => {
+                let years = self.works.iter().map(|work| work.year.clone());
+                write!(f, "{}", years.collect::<Vec<_>>().join(", "))
+            }
+            // the text of a footnote, e.g. `Knuth, 1984, p. 3.`
+            CiteStyle::Foot => {
+                let works = self
+                    .works
+                    .iter()
+                    .map(|work| format!("{}, {}", work.author, work.year));
+                let works = works.collect::<Vec<_>>().join("; ");
+                write!(f, "{prenote}{works}{postnote}.")
+            }
+        }
+    }
+}
+
+#[test]
+fn test_from_key() {
+    assert_eq!(author_from_key("smith2020deep"), "Smith");
+    assert_eq!(author_from_key("DBLP:conf/x/Smith20"), "Smith");
+    assert_eq!(year_from_key("smith2020deep"), "2020");
+    assert_eq!(year_from_key("knuth84"), "1984");
+    assert_eq!(year_from_key("smith"), "n.d.");
+}
+
+#[test]
+fn test_from_entry() {
+    let db = bib::Database::parse(
+        r"@book{knuth84, author = {Knuth, Donald E.}, year = 1984}
+@article{three, author = {A. One and B. Two and {C. Three}}, date = {2001-05}}
+@article{two, author = {{van Leeuwen}, Jan and Leslie Lamport}}
+@article{wrapped, author = {Leslie Lamport and
+    Smith, John and
+    Jones, Ann}}",
+    );
+    let knuth = db.get("knuth84").unwrap();
+    assert_eq!(author_from_entry(knuth).as_deref(), Some("Knuth"));
+    assert_eq!(year_from_entry(knuth).as_deref(), Some("1984"));
+    let three = db.get("three").unwrap();
+    assert_eq!(author_from_entry(three).as_deref(), Some("One et al."));
+    assert_eq!(year_from_entry(three).as_deref(), Some("2001"));
+    let two = db.get("two").unwrap();
+    assert_eq!(
+        author_from_entry(two).as_deref(),
+        Some("van Leeuwen and Lamport")
+    );
+    let wrapped = db.get("wrapped").unwrap();
+    assert_eq!(
+        author_from_entry(wrapped).as_deref(),
+        Some("Lamport et al.")
+    );
+}
+
+#[test]
+fn test_aux_labels() {
+    let aux = r"\bibcite{knuth84}{{1}{1984}{{Knuth}}{{Knuth}}}";
+    let convert = |preamble| {
+        let preamble = token::Document::from_str(preamble).unwrap();
+        let doc = token::Document::from_str(r"\citep{knuth84}").unwrap();
+        let resolver = super::Resolver::from_aux(aux);
+        format!("{}", super::convert(Some(preamble), doc, resolver))
+    };
+    assert_eq!(convert(r"\usepackage{natbib}"), "[Knuth, 1984]");
+    assert_eq!(convert(r"\usepackage[numbers]{natbib}"), "[1]");
+}
+
+#[test]
+fn test_footcite() {
+    let doc = token::Document::from_str(r"A claim.\footcite[p.~3]{knuth84} Next.").unwrap();
+    let mut ast = super::convert(None, doc, super::Resolver::default());
+    super::place_footnotes(&mut ast, super::Placement::After);
+    assert_eq!(
+        format!("{ast}"),
+        "A claim.[^1] Next.\n\n[^1]: Knuth, 1984, p. 3."
+    );
+}
@@ -1,63 +1,286 @@
 use std::fmt;
 
-use super::{make_upper_substitute, token_to_ast::token_to_ast, Paragraph};
+use super::cite::{Cite, Work};
+use super::float::Caption;
+use super::footnote::Footnote;
+use super::reference::{reference, reference_range};
+use super::{token_to_ast::token_to_ast, Context, Paragraph};
 use crate::token;
 
-use super::{Ast, Word};
+use super::{Ast, Text, Word};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Command {
+    /// A heading by its depth, and its title.
     Section(u8, Ast),
     Label,
-    Cite(String),
+    Cite(Cite),
     Ref(String),
     Item,
+    LineBreak,
+    Caption(Caption),
+    Footnote(Footnote),
+    /// The author block of `\maketitle`.
+    Authors(Ast),
 }
 
 pub(super) fn token_to_ast_command(
     c: token::Command,
-    mut args: Vec<token::Document>,
+    mut args: Vec<Option<token::Document>>,
+    ctx: &mut Context,
 ) -> Option<Word> {
     let w = match c {
-        token::Command::Section(n) => {
-            assert_eq!(args.len(), 1);
-            Word::Command(Command::Section(n, token_to_ast(args.remove(0))))
+        token::Command::Section(level) => {
+            assert_eq!(args.len(), 3);
+            let depth = ctx.enter_section(level, args[0].is_some());
+            // the short title is only for the table of contents
+            Word::Command(Command::Section(
+                depth,
+                token_to_ast(take(&mut args, 2), ctx),
+            ))
         }
-        token::Command::Label => return None,
-        token::Command::Cite => {
+        token::Command::Label => {
             assert_eq!(args.len(), 1);
-            let s = format!("{}", args[0]);
-            Word::Command(Command::Cite(make_upper_substitute(s)))
+            ctx.define_label(key(take(&mut args, 0)));
+            return None;
         }
-        token::Command::Ref => {
-            assert_eq!(args.len(), 1);
-            let s = format!("{}", args[0]);
-            let name = make_ref_name(s);
-            Word::Command(Command::Ref(format!("{} 7", name)))
+        token::Command::Cite(style) => {
+            assert_eq!(args.len(), 4);
+            let keys = key(take(&mut args, 3));
+            for key in keys.split(',') {
+                ctx.cite(key);
+            }
+            let works = keys.split(',').map(|key| Work::new(key, ctx)).collect();
+            let mut note = |i: usize| {
+                let note = format!("{}", token_to_ast(args[i].take()?, ctx));
+                Some(note.trim().to_string())
+            };
+            // natbib and biblatex: a single optional argument is a postnote
+            let (prenote, postnote) = match note(2) {
+                Some(postnote) => (note(1), Some(postnote)),
+                None => (None, note(1)),
+            };
+            let cite = Word::Command(Command::Cite(Cite {
+                style,
+                works,
+                prenote,
+                postnote,
+            }));
+            if style != token::CiteStyle::Foot {
+                return Some(cite);
+            }
+            // the citation is the text of a footnote
+            let number = ctx.enter_footnote(None);
+            ctx.leave_env();
+            let text = Ast(vec![Paragraph(vec![cite])]);
+            Word::Command(Command::Footnote(Footnote { number, text }))
+        }
+        token::Command::Ref(style) => {
+            assert_eq!(args.len(), 2);
+            let keys = key(take(&mut args, 1));
+            Word::Command(Command::Ref(reference(style, &keys, ctx)))
+        }
+        token::Command::RefRange(style) => {
+            assert_eq!(args.len(), 3);
+            let first = key(take(&mut args, 1));
+            let last = key(take(&mut args, 2));
+            Word::Command(Command::Ref(reference_range(style, &first, &last, ctx)))
         }
         token::Command::Font => {
             assert_eq!(args.len(), 1);
-            Word::Lines(token_to_ast(args.remove(0)))
+            Word::Lines(token_to_ast(take(&mut args, 0), ctx))
+        }
+        token::Command::Quote => {
+            assert_eq!(args.len(), 2);
+            // written as in TeX, for the normalization to choose the style
+            let (open, close) = match args[0].is_some() {
+                true => ("`", "'"),
+                false => ("``", "''"),
+            };
+            let mut ast = token_to_ast(take(&mut args, 1), ctx);
+            enclose(&mut ast, open, close);
+            Word::Lines(ast)
         }
         token::Command::Item => {
             assert_eq!(args.len(), 0);
             Word::Command(Command::Item)
         }
         token::Command::Space => return None,
+        token::Command::NewTheorem => {
+            assert_eq!(args.len(), 5);
+            let numbered = args[0].is_none();
+            let env = key(take(&mut args, 1));
+            let shared = args[2].take().map(key);
+            let name = format!("{}", token_to_ast(take(&mut args, 3), ctx));
+            let within = args[4].take().map(key);
+            ctx.new_theorem(env, name.trim().to_string(), numbered, shared, within);
+            return None;
+        }
+        token::Command::NumberWithin => {
+            assert_eq!(args.len(), 3);
+            let counter = key(take(&mut args, 1));
+            ctx.number_within(&counter, &key(take(&mut args, 2)));
+            return None;
+        }
+        token::Command::NoNumber => {
+            ctx.no_number();
+            return None;
+        }
+        token::Command::Appendix => {
+            ctx.enter_appendix();
+            return None;
+        }
+        token::Command::UsePackage => {
+            assert_eq!(args.len(), 2);
+            let options = args[0].take().map(key).unwrap_or_default();
+            let options = options.split(',').map(str::to_string).collect::<Vec<_>>();
+            for name in key(take(&mut args, 1)).split(',') {
+                ctx.use_package(name.to_string(), options.clone());
+            }
+            return None;
+        }
+        token::Command::Caption => {
+            assert_eq!(args.len(), 3);
+            let name = match args[0] {
+                Some(_) => None,
+                None => ctx.enter_caption(),
+            };
+            // the short caption is only for the list of figures
+            let text = token_to_ast(take(&mut args, 2), ctx);
+            Word::Command(Command::Caption(Caption { name, text }))
+        }
+        token::Command::Footnote => {
+            assert_eq!(args.len(), 2);
+            let number = args[0].take().map(key);
+            let number = ctx.enter_footnote(number);
+            let text = token_to_ast(take(&mut args, 1), ctx);
+            ctx.leave_env();
+            Word::Command(Command::Footnote(Footnote { number, text }))
+        }
+        token::Command::Title => {
+            assert_eq!(args.len(), 2);
+            let title = token_to_ast(take(&mut args, 1), ctx);
+            ctx.set_title(title);
+            return None;
+        }
+        token::Command::Author => {
+            assert_eq!(args.len(), 2);
+            let authors = token_to_ast(take(&mut args, 1), ctx);
+            ctx.set_authors(authors);
+            return None;
+        }
+        // written by `token_to_ast` as it makes paragraphs, as is beamer's `\titlepage`
+        token::Command::MakeTitle | token::Command::TitlePage => return None,
+        // the framed box of LaTeX, a frame of beamer being written by `token_to_ast`
+        token::Command::Frame => {
+            assert_eq!(args.len(), 2);
+            Word::Lines(token_to_ast(take(&mut args, 1), ctx))
+        }
+        // rendered as the `keywords` environment
+        token::Command::Keywords => {
+            assert_eq!(args.len(), 1);
+            let keywords = token_to_ast(take(&mut args, 0), ctx);
+            Word::Env("keywords".to_string(), keywords)
+        }
+        token::Command::FrameTitle => {
+            assert_eq!(args.len(), 2);
+            let title = token_to_ast(take(&mut args, 1), ctx);
+            ctx.set_frame_title(title);
+            return None;
+        }
+        token::Command::DocumentClass => {
+            assert_eq!(args.len(), 2);
+            ctx.set_class(&key(take(&mut args, 1)));
+            return None;
+        }
+        token::Command::Bibliography => {
+            assert_eq!(args.len(), 2);
+            for name in key(take(&mut args, 1)).split(',') {
+                ctx.add_bibliography(name.to_string());
+            }
+            return None;
+        }
+        token::Command::Known(sig) => {
+            let mut mandatory = sig
+                .spec
+                .chars()
+                .zip(args)
+                .filter_map(|(kind, arg)| if kind == 'm' { arg } else { None })
+                .collect::<Vec<_>>();
+            match sig.class {
+                token::Class::Layout | token::Class::Drop => return None,
+                // a lone argument is the link target itself
+                token::Class::Reference if mandatory.len() == 1 => {
+                    let target = format!("{}", mandatory.remove(0));
+                    Word::Text(Text::new(target.trim()))
+                }
+                token::Class::Reference | token::Class::Font => {
+                    Word::Lines(token_to_ast(mandatory.pop()?, ctx))
+                }
+            }
+        }
         token::Command::Unknown(s) => {
             let mut ps = Paragraph::new();
-            ps.push(Word::Text(s));
-            for arg in args {
-                ps.push(Word::Lines(token_to_ast(arg)));
+            ps.push(Word::Text(Text::new(s)));
+            for arg in args.into_iter().flatten() {
+                ps.push(Word::Lines(token_to_ast(arg, ctx)));
             }
             Word::Lines(Ast(vec![ps]))
         }
-        // TODO: handle symbols
+        token::Command::Symbol('\\') | token::Command::LineBreak if ctx.in_rows() => {
+            ctx.new_row();
+            return None;
+        }
+        token::Command::Symbol('\\') | token::Command::LineBreak => {
+            Word::Command(Command::LineBreak)
+        }
+        // an escaped character which does not start a word, e.g. `\{` after a group
+        token::Command::Symbol(ch) if token::ESCAPED.contains(&ch) => Word::Text(Text::new(ch)),
+        // spacing commands such as `\,` and `\!` separate words anyway
         token::Command::Symbol(_) => return None,
     };
     Some(w)
 }
 
+/// Puts quotes around a text, attached to its first and last words.
+fn enclose(ast: &mut Ast, open: &str, close: &str) {
+    if ast.0.is_empty() {
+        ast.push(Paragraph::new());
+    }
+    if !attach(ast, open, true) {
+        ast.0[0].0.insert(0, Word::Text(Text::new(open)));
+    }
+    if !attach(ast, close, false) {
+        let last = ast.0.len() - 1;
+        ast.0[last].push(Word::Text(Text::new(close)));
+    }
+}
+
+/// Attaches `s` to the first or the last word of `ast` if it is text, even in a group.
+fn attach(ast: &mut Ast, s: &str, first: bool) -> bool {
+    let word = match first {
+        true => ast.0.first_mut().and_then(|p| p.0.first_mut()),
+        false => ast.0.last_mut().and_then(|p| p.0.last_mut()),
+    };
+    match word {
+        Some(Word::Text(text)) if first => text.s.insert_str(0, s),
+        Some(Word::Text(text)) => text.s.push_str(s),
+        Some(Word::Lines(ast)) => return attach(ast, s, first),
+        _ => return false,
+    }
+    true
+}
+
+/// Reads a key such as a label from an argument.
+fn key(doc: token::Document) -> String {
+    format!("{doc}").split_whitespace().collect()
+}
+
+/// Takes the `i`-th argument, treating an absent one as empty.
+fn take(args: &mut [Option<token::Document>], i: usize) -> token::Document {
+    args[i].take().unwrap_or_default()
+}
+
 impl fmt::Display for Command {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -65,38 +288,16 @@ impl fmt::Display for Command {
                 for _ in 0..*n {
                     write!(f, "#")?
                 }
-                writeln!(f, " {ast}")
+                write!(f, " {ast}")
             }
             Command::Label => Ok(()),
-            Command::Cite(s) => {
-                write!(f, "[{s}]")
-            }
+            Command::Cite(cite) => write!(f, "{cite}"),
             Command::Ref(s) => write!(f, "{s}"),
-            Command::Item => write!(f, "\n  - "),
+            Command::Item => write!(f, "\n- "),
+            Command::LineBreak => writeln!(f),
+            Command::Caption(caption) => write!(f, "{caption}"),
+            Command::Footnote(footnote) => write!(f, "{footnote}"),
+            Command::Authors(ast) => write!(f, "{ast}"),
         }
     }
 }
-
-fn make_ref_name(s: String) -> String {
-    let mut s = s
-        .chars()
-        .filter(|c| c.is_alphabetic())
-        .collect::<String>()
-        .to_lowercase();
-    s.truncate(3);
-    let new_name = match &*s {
-        "sec" => "Section",
-        "sub" => "Section",
-        "thm" | "the" => "Theorem",
-        "lem" => "Lemma",
-        "pro" | "prp" => "Proposition",
-        "cor" => "Corollary",
-        "def" => "Definition",
-        c if c.strip_prefix("eg").is_some() => "Example",
-        c if c.strip_prefix("ex").is_some() => "Example",
-        c if c.strip_prefix("ax").is_some() => "Axiom",
-        "rem" | "rmk" => "Remark",
-        _ => "Theorem",
-    };
-    new_name.to_owned()
-}
@@ -0,0 +1,476 @@
+use std::collections::HashMap;
+
+use super::counter::Counters;
+use super::label::{Labels, Target};
+use super::resolver::{Citation, Resolver};
+use super::Ast;
+use crate::bib;
+
+/// The state of a conversion: definitions from the preamble,
+/// the current position in the document and the labels.
+#[derive(Debug, Default)]
+pub struct Context {
+    /// Packages loaded by `\usepackage`, with their options.
+    packages: HashMap<String, Vec<String>>,
+    /// Theorem-like environments defined by `\newtheorem`.
+    theorems: HashMap<String, Theorem>,
+    counters: Counters,
+    /// The environments enclosing the current position, innermost last,
+    /// with the label target to restore when leaving them.
+    envs: Vec<(String, Option<Target>)>,
+    /// What a `\label` at the current position refers to, like `\@currentlabel`.
+    current: Option<Target>,
+    /// Whether `\appendix` has been seen.
+    appendix: bool,
+    /// Whether the document class has chapters, like `book` and `report`.
+    chapters: bool,
+    /// Whether the document class is `beamer`.
+    beamer: bool,
+    /// The shallowest sectioning level used in this pass.
+    shallowest: Option<u8>,
+    /// The shallowest sectioning level used in the previous pass.
+    top: Option<u8>,
+    /// The title and the authors given by `\title` and `\author`, until `\maketitle`.
+    title: Option<Ast>,
+    authors: Option<Ast>,
+    /// Whether `\maketitle` has made a title heading in this pass.
+    made_title: bool,
+    /// Whether it did in the previous pass, putting the sections under the title.
+    titled: bool,
+    /// The depth of the heading of the current section.
+    depth: Option<u8>,
+    /// The title given by beamer's `\frametitle` in the current frame.
+    frame_title: Option<Ast>,
+    /// Labels defined in this pass.
+    labels: Labels,
+    /// Cited keys in the order of their first citation.
+    cited: Vec<String>,
+    /// `.bib` files named by `\bibliography` or `\addbibresource`.
+    bibliographies: Vec<String>,
+    resolver: Resolver,
+}
+
+#[derive(Debug)]
+struct Theorem {
+    name: String,
+    /// `None` for unnumbered theorems defined by `\newtheorem*`.
+    counter: Option<String>,
+}
+
+/// Sectioning counters by level.
+const SECTIONS: [&str; 7] = [
+    "part",
+    "chapter",
+    "section",
+    "subsection",
+    "subsubsection",
+    "paragraph",
+    "subparagraph",
+];
+
+/// Common theorem-like environments, known unnumbered when not defined by `\newtheorem`,
+/// e.g. by a class file.
+const THEOREMS: [(&str, &str); 16] = [
+    ("theorem", "Theorem"),
+    ("thm", "Theorem"),
+    ("lemma", "Lemma"),
+    ("lem", "Lemma"),
+    ("proposition", "Proposition"),
+    ("prop", "Proposition"),
+    ("corollary", "Corollary"),
+    ("cor", "Corollary"),
+    ("definition", "Definition"),
+    ("defn", "Definition"),
+    ("remark", "Remark"),
+    ("rem", "Remark"),
+    ("example", "Example"),
+    ("conjecture", "Conjecture"),
+    ("claim", "Claim"),
+    ("assumption", "Assumption"),
+];
+
+/// List environments.
+const LISTS: [&str; 3] = ["itemize", "enumerate", "description"];
+
+/// Float environments and their counters.
+const FLOATS: [(&str, &str); 8] = [
+    ("figure", "figure"),
+    ("figure*", "figure"),
+    ("wrapfigure", "figure"),
+    ("sidewaysfigure", "figure"),
+    ("table", "table"),
+    ("table*", "table"),
+    ("wraptable", "table"),
+    ("sidewaystable", "table"),
+];
+
+/// The counter of a float environment such as `figure*`.
+pub fn float_counter(env: &str) -> Option<&'static str> {
+    FLOATS
+        .iter()
+        .find(|(float, _)| *float == env)
+        .map(|(_, counter)| *counter)
+}
+
+/// Document classes with chapters.
+const CHAPTER_CLASSES: [&str; 5] = ["book", "report", "memoir", "scrbook", "scrreprt"];
+
+/// Display math environments numbered once per environment.
+const EQUATIONS: [&str; 2] = ["equation", "multline"];
+
+/// Display math environments numbered once per row.
+const ROWS: [&str; 5] = ["align", "gather", "flalign", "alignat", "eqnarray"];
+
+impl Context {
+    pub fn new(resolver: Resolver) -> Self {
+        Context {
+            resolver,
+            ..Default::default()
+        }
+    }
+
+    /// Starts a new pass over the document, which knows the labels of this one.
+    pub fn next_pass(self) -> Self {
+        let mut resolver = self
+            .resolver
+            .with_labels(self.labels)
+            .with_cited(self.cited);
+        resolver.load_bibliographies(&self.bibliographies);
+        Context {
+            top: self.shallowest,
+            titled: self.made_title,
+            ..Context::new(resolver)
+        }
+    }
+
+    /// Sets up the counters of a document class: `book` and `report` number within chapters.
+    pub fn set_class(&mut self, class: &str) {
+        self.chapters = CHAPTER_CLASSES.contains(&class);
+        self.beamer = class == "beamer";
+        if self.chapters {
+            self.counters.define("chapter", None);
+            for counter in ["section", "equation", "figure", "table"] {
+                self.counters.define(counter, Some("chapter"));
+            }
+        }
+    }
+
+    pub fn use_package(&mut self, name: String, options: Vec<String>) {
+        self.packages.insert(name, options);
+    }
+
+    pub fn has_package_option(&self, name: &str, option: &str) -> bool {
+        self.packages
+            .get(name)
+            .is_some_and(|options| options.iter().any(|o| o == option))
+    }
+
+    /// Defines a theorem-like environment as `\newtheorem` does.
+    /// It shares the counter `shared` or gets its own one, numbered within `within`.
+    pub fn new_theorem(
+        &mut self,
+        env: String,
+        name: String,
+        numbered: bool,
+        shared: Option<String>,
+        within: Option<String>,
+    ) {
+        let counter = match (numbered, shared) {
+            (false, _) => None,
+            (true, Some(shared)) => Some(shared),
+            (true, None) => {
+                self.counters.define(&env, within.as_deref());
+                Some(env.clone())
+            }
+        };
+        self.theorems.insert(env, Theorem { name, counter });
+    }
+
+    /// The name and the number of the current theorem-like environment `env`, e.g. `Theorem 2.1`.
+    pub fn theorem_name(&self, env: &str) -> Option<String> {
+        let Some(theorem) = self.theorems.get(env) else {
+            let (_, name) = THEOREMS.iter().find(|(known, _)| *known == env)?;
+            return Some(name.to_string());
+        };
+        match &theorem.counter {
+            Some(counter) => Some(format!("{} {}", theorem.name, self.counters.the(counter))),
+            None => Some(theorem.name.clone()),
+        }
+    }
+
+    /// Numbers `counter` within `within` as `\numberwithin` does.
+    pub fn number_within(&mut self, counter: &str, within: &str) {
+        self.counters.define(counter, Some(within));
+    }
+
+    pub fn enter_env(&mut self, env: &str) {
+        self.envs.push((env.to_string(), self.current.clone()));
+        if let Some(Theorem {
+            counter: Some(counter),
+            ..
+        }) = self.theorems.get(env)
+        {
+            let counter = counter.clone();
+            self.step(&counter, env);
+        } else if EQUATIONS.contains(&env) || ROWS.contains(&env) {
+            self.step("equation", "equation");
+        } else if !self.theorems.contains_key(env)
+            && THEOREMS.iter().any(|(known, _)| *known == env)
+        {
+            // defined by a class file, so numbered in a way which is not known
+            // and referred to by its name alone
+            self.current = Some(Target {
+                kind: env.to_string(),
+                number: String::new(),
+                page: None,
+            });
+        }
+    }
+
+    pub fn leave_env(&mut self) {
+        if let Some((_, current)) = self.envs.pop() {
+            self.current = current;
+        }
+    }
+
+    /// Numbers a `\caption` by the innermost float, e.g. `Figure 3`.
+    /// The counter of a float is stepped by its captions, as in LaTeX.
+    pub fn enter_caption(&mut self) -> Option<String> {
+        let counter = self
+            .envs
+            .iter()
+            .rev()
+            .find_map(|(env, _)| float_counter(env))?;
+        let name = self.kind_name(counter)?;
+        self.step(counter, counter);
+        Some(format!("{name} {}", self.counters.the(counter)))
+    }
+
+    /// Starts a footnote, numbered by its counter unless `number` is given, and returns its number.
+    /// It ends with [`Context::leave_env`].
+    pub fn enter_footnote(&mut self, number: Option<String>) -> String {
+        self.envs
+            .push(("footnote".to_string(), self.current.clone()));
+        let number = number.unwrap_or_else(|| {
+            self.counters.step("footnote");
+            self.counters.the("footnote")
+        });
+        self.current = Some(Target {
+            kind: "footnote".to_string(),
+            number: number.clone(),
+            page: None,
+        });
+        number
+    }
+
+    /// The number of lists enclosing the current position.
+    pub fn list_depth(&self) -> usize {
+        self.envs
+            .iter()
+            .filter(|(env, _)| LISTS.contains(&env.as_str()))
+            .count()
+    }
+
+    /// The number of `enumerate` lists enclosing the current position.
+    pub fn enumerate_depth(&self) -> usize {
+        self.envs
+            .iter()
+            .filter(|(env, _)| env == "enumerate")
+            .count()
+    }
+
+    /// Starts an item of an `enumerate` list numbered by `counter`, such as `enumi`.
+    pub fn enter_item(&mut self, counter: &str, number: &str) {
+        self.current = Some(Target {
+            kind: counter.to_string(),
+            number: number.to_string(),
+            page: None,
+        });
+    }
+
+    /// Whether the innermost environment is display math numbered per row.
+    pub fn in_rows(&self) -> bool {
+        matches!(self.envs.last(), Some((env, _)) if ROWS.contains(&env.as_str()))
+    }
+
+    /// Starts a new row of a display math environment at `\\`.
+    pub fn new_row(&mut self) {
+        if self.in_rows() {
+            self.step("equation", "equation");
+        }
+    }
+
+    /// Leaves the current equation unnumbered at `\nonumber` or `\notag`.
+    pub fn no_number(&mut self) {
+        if self.current.as_ref().map(|target| &*target.kind) == Some("equation") {
+            self.counters.unstep("equation");
+            self.current = None;
+        }
+    }
+
+    /// Starts a section at a level of [`SECTIONS`], numbered unless it is starred.
+    /// Returns the depth of its heading, 1 for the outermost level of the document.
+    pub fn enter_section(&mut self, level: u8, starred: bool) -> u8 {
+        let shallowest = self.shallowest.get_or_insert(level);
+        *shallowest = (*shallowest).min(level);
+        let top = self.top_level();
+        let top = self.top.map_or(top, |used| used.min(top));
+        let depth = level.saturating_sub(top) + self.top_depth();
+        self.depth = Some(depth);
+        let Some(counter) = SECTIONS.get(level as usize) else {
+            return depth;
+        };
+        if starred {
+            return depth;
+        }
+        let kind = match self.appendix && level >= self.top_level() {
+            // cleveref's `appendix`, `subappendix`, ...
+            true => "sub".repeat((level - self.top_level()) as usize) + "appendix",
+            false => counter.to_string(),
+        };
+        self.step(counter, &kind);
+        depth
+    }
+
+    /// The depth of the headings of the outermost sections, below the title if there is one.
+    pub fn top_depth(&self) -> u8 {
+        1 + u8::from(self.titled)
+    }
+
+    pub fn set_title(&mut self, title: Ast) {
+        self.title = Some(title);
+    }
+
+    pub fn set_authors(&mut self, authors: Ast) {
+        self.authors = Some(authors);
+    }
+
+    /// Takes the title and the authors for `\maketitle`.
+    pub fn make_title(&mut self) -> (Option<Ast>, Option<Ast>) {
+        self.made_title |= self.title.is_some();
+        (self.title.take(), self.authors.take())
+    }
+
+    pub fn is_beamer(&self) -> bool {
+        self.beamer
+    }
+
+    /// The depth of the heading of a beamer frame, below the current section.
+    pub fn frame_depth(&self) -> u8 {
+        self.depth.map_or(self.top_depth(), |depth| depth + 1)
+    }
+
+    pub fn set_frame_title(&mut self, title: Ast) {
+        self.frame_title = Some(title);
+    }
+
+    /// Takes the title given by `\frametitle` at the end of a frame.
+    pub fn take_frame_title(&mut self) -> Option<Ast> {
+        self.frame_title.take()
+    }
+
+    /// The level of chapters or sections, whichever the document class has at the top.
+    fn top_level(&self) -> u8 {
+        match self.chapters {
+            true => 1,
+            false => 2,
+        }
+    }
+
+    /// Starts the appendix: the top sectioning level is lettered from now on.
+    pub fn enter_appendix(&mut self) {
+        self.appendix = true;
+        self.counters.appendix(SECTIONS[self.top_level() as usize]);
+    }
+
+    /// Records a `\label` at the current position.
+    pub fn define_label(&mut self, key: String) {
+        match &self.current {
+            Some(target) => self.labels.insert(key, target.clone()),
+            None => log::warn!("label `{key}` refers to nothing"),
+        }
+    }
+
+    pub fn reference(&self, key: &str) -> Option<&Target> {
+        self.resolver.reference(key)
+    }
+
+    pub fn citation(&self, key: &str) -> Option<&Citation> {
+        self.resolver.citation(key)
+    }
+
+    pub fn add_bibliography(&mut self, name: String) {
+        if !self.bibliographies.contains(&name) {
+            self.bibliographies.push(name);
+        }
+    }
+
+    /// Records a citation of `key`.
+    pub fn cite(&mut self, key: &str) {
+        if !self.cited.iter().any(|cited| cited == key) {
+            self.cited.push(key.to_string());
+        }
+    }
+
+    pub fn bib_entry(&self, key: &str) -> Option<&bib::Entry> {
+        self.resolver.bib_entry(key)
+    }
+
+    /// Whether citations are labelled by authors and years, as with natbib's default style.
+    pub fn is_author_year(&self) -> bool {
+        self.packages
+            .get("natbib")
+            .is_some_and(|options| !options.iter().any(|o| o == "numbers"))
+            || self
+                .packages
+                .get("biblatex")
+                .is_some_and(|options| options.iter().any(|o| o.starts_with("style=authoryear")))
+    }
+
+    /// The label of a work in the bibliography: its number in the order of citation,
+    /// or its authors and year with author-year styles.
+    pub fn bib_label(&self, key: &str, author: &str, year: &str) -> Option<String> {
+        self.bib_entry(key)?;
+        match self.is_author_year() {
+            true => Some(format!("{author}, {year}")),
+            false => self.resolver.citation_number(key).map(|n| n.to_string()),
+        }
+    }
+
+    /// The name of a kind of target used by `\cref`, e.g. `Lemma` for `lemma`.
+    pub fn kind_name(&self, kind: &str) -> Option<String> {
+        if let Some(theorem) = self.theorems.get(kind) {
+            return Some(theorem.name.clone());
+        }
+        if let Some((_, name)) = THEOREMS.iter().find(|(known, _)| *known == kind) {
+            return Some(name.to_string());
+        }
+        let name = match kind {
+            "" => return None,
+            "part" => "Part",
+            "chapter" => "Chapter",
+            _ if SECTIONS.contains(&kind) => "Section",
+            _ if kind.ends_with("appendix") => "Appendix",
+            "equation" => "Equation",
+            "figure" => "Figure",
+            "table" => "Table",
+            "enumi" | "enumii" | "enumiii" | "enumiv" => "Item",
+            "footnote" => "Footnote",
+            _ => {
+                let mut chars = kind.chars();
+                let first = chars.next()?.to_uppercase();
+                return Some(first.chain(chars).collect());
+            }
+        };
+        Some(name.to_string())
+    }
+
+    fn step(&mut self, counter: &str, kind: &str) {
+        self.counters.step(counter);
+        self.current = Some(Target {
+            kind: kind.to_string(),
+            number: self.counters.the(counter),
+            page: None,
+        });
+    }
+}
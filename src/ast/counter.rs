@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+/// LaTeX-like counters. Stepping a counter resets the counters numbered within it,
+/// and the number of a counter is prefixed by the number of its parent, e.g. `2.3`.
+#[derive(Debug)]
+pub struct Counters(HashMap<String, Counter>);
+
+#[derive(Debug, Default)]
+struct Counter {
+    value: u32,
+    within: Option<String>,
+    /// Numbered `A`, `B`, ... as in the appendix.
+    alph: bool,
+}
+
+impl Default for Counters {
+    /// The counters of the `article` class.
+    fn default() -> Self {
+        let mut counters = Counters(HashMap::new());
+        counters.define("part", None);
+        counters.define("section", None);
+        counters.define("subsection", Some("section"));
+        counters.define("subsubsection", Some("subsection"));
+        counters.define("paragraph", Some("subsubsection"));
+        counters.define("subparagraph", Some("paragraph"));
+        counters.define("equation", None);
+        counters.define("figure", None);
+        counters.define("table", None);
+        counters.define("footnote", None);
+        counters
+    }
+}
+
+impl Counters {
+    /// Defines a counter, or changes the parent of an existing one as `\numberwithin` does.
+    pub fn define(&mut self, name: &str, within: Option<&str>) {
+        let counter = self.0.entry(name.to_string()).or_default();
+        counter.within = within.map(str::to_string);
+    }
+
+    pub fn step(&mut self, name: &str) {
+        match self.0.get_mut(name) {
+            Some(counter) => counter.value += 1,
+            None => {
+                log::warn!("no counter `{name}`");
+                return;
+            }
+        }
+        self.reset_within(name);
+    }
+
+    /// Undoes a step, e.g. for an equation marked `\nonumber`.
+    pub fn unstep(&mut self, name: &str) {
+        if let Some(counter) = self.0.get_mut(name) {
+            counter.value = counter.value.saturating_sub(1);
+        }
+    }
+
+    /// Restarts a counter with letters, as `\appendix` does for the top sectioning level.
+    pub fn appendix(&mut self, name: &str) {
+        if let Some(counter) = self.0.get_mut(name) {
+            counter.value = 0;
+            counter.alph = true;
+        }
+        self.reset_within(name);
+    }
+
+    /// The number of a counter as `\the<counter>` prints it.
+    pub fn the(&self, name: &str) -> String {
+        let Some(counter) = self.0.get(name) else {
+            return String::new();
+        };
+        let value = match counter.alph {
+            true => alph(counter.value),
+            false => counter.value.to_string(),
+        };
+        match &counter.within {
+            Some(parent) => format!("{}.{value}", self.the(parent)),
+            None => value,
+        }
+    }
+
+    fn reset_within(&mut self, name: &str) {
+        let children = self
+            .0
+            .iter()
+            .filter(|(_, counter)| counter.within.as_deref() == Some(name))
+            .map(|(child, _)| child.clone())
+            .collect::<Vec<_>>();
+        for child in children {
+            if let Some(counter) = self.0.get_mut(&child) {
+                counter.value = 0;
+            }
+            self.reset_within(&child);
+        }
+    }
+}
+
+pub(super) fn alph(n: u32) -> String {
+    match n {
+        1..=26 => char::from(b'A' + (n - 1) as u8).to_string(),
+        _ => n.to_string(),
+    }
+}
+
+#[test]
+fn test_counters() {
+    let mut counters = Counters::default();
+    counters.define("thm", Some("section"));
+    counters.step("section");
+    counters.step("thm");
+    counters.step("thm");
+    assert_eq!(counters.the("thm"), "1.2");
+    counters.step("section");
+    counters.step("subsection");
+    assert_eq!(counters.the("subsection"), "2.1");
+    counters.step("thm");
+    assert_eq!(counters.the("thm"), "2.1");
+    counters.appendix("section");
+    counters.step("section");
+    counters.step("subsection");
+    assert_eq!(counters.the("subsection"), "A.1");
+}
@@ -0,0 +1,283 @@
+use std::fmt;
+
+use super::command::Command;
+use super::{Ast, Float, List, Paragraph, Table, Theorem, Word};
+
+/// A converted document as a tree of sections, built from the flat paragraphs of an [`Ast`].
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct Document {
+    /// The blocks before the first heading.
+    pub blocks: Vec<Block>,
+    pub sections: Vec<Section>,
+}
+
+/// A section with its blocks and subsections.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Section {
+    /// The depth of the heading, 1 for the outermost sectioning level of the document.
+    pub level: u8,
+    pub title: Ast,
+    /// The blocks before the first subsection.
+    pub blocks: Vec<Block>,
+    pub sections: Vec<Section>,
+}
+
+/// A paragraph, a list, a theorem, a float, a table or another environment
+/// which stands on its own.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Block {
+    Paragraph(Paragraph),
+    Env(String, Ast),
+    Theorem(Theorem),
+    List(List),
+    Float(Float),
+    Table(Table),
+}
+
+impl From<Ast> for Document {
+    fn from(ast: Ast) -> Self {
+        let mut document = Document::default();
+        // the sections from the outermost one to the current one
+        let mut open: Vec<Section> = Vec::new();
+        for paragraph in ast.0 {
+            match heading(paragraph) {
+                Ok((level, title)) => {
+                    close(&mut document, &mut open, level);
+                    open.push(Section {
+                        level,
+                        title,
+                        blocks: Vec::new(),
+                        sections: Vec::new(),
+                    });
+                }
+                Err(paragraph) => match open.last_mut() {
+                    Some(section) => section.blocks.extend(blocks(paragraph)),
+                    None => document.blocks.extend(blocks(paragraph)),
+                },
+            }
+        }
+        close(&mut document, &mut open, 0);
+        document
+    }
+}
+
+/// Splits a heading paragraph into its level and title, or gives the paragraph back.
+fn heading(paragraph: Paragraph) -> Result<(u8, Ast), Paragraph> {
+    match <[Word; 1]>::try_from(paragraph.0) {
+        Ok([Word::Command(Command::Section(level, title))]) => Ok((level, title)),
+        Ok(word) => Err(Paragraph(word.into())),
+        Err(words) => Err(Paragraph(words)),
+    }
+}
+
+/// Closes the open sections at `level` or deeper, adding each one to its parent.
+fn close(document: &mut Document, open: &mut Vec<Section>, level: u8) {
+    while open.last().is_some_and(|section| section.level >= level) {
+        let section = open.pop().unwrap();
+        match open.last_mut() {
+            Some(parent) => parent.sections.push(section),
+            None => document.sections.push(section),
+        }
+    }
+}
+
+/// Splits a paragraph into blocks: theorems and the environments out of display math stand on their own,
+/// between the paragraphs of the words around them.
+fn blocks(paragraph: Paragraph) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut words = Vec::new();
+    for word in paragraph.0 {
+        let block = match word {
+            Word::Env(env, ast) if !super::env::is_math(&env) => Block::Env(env, ast),
+            Word::Theorem(theorem) => Block::Theorem(theorem),
+            Word::List(list) => Block::List(list),
+            // a float without a caption or a table is dropped
+            Word::Float(float) if float.is_empty() => continue,
+            Word::Float(float) => Block::Float(float),
+            Word::Table(table) => Block::Table(table),
+            word => {
+                words.push(word);
+                continue;
+            }
+        };
+        if !words.is_empty() {
+            blocks.push(Block::Paragraph(Paragraph(std::mem::take(&mut words))));
+        }
+        blocks.push(block);
+    }
+    if !words.is_empty() {
+        blocks.push(Block::Paragraph(Paragraph(words)));
+    }
+    blocks
+}
+
+impl Document {
+    /// Finds a section by its title, at any depth.
+    pub fn section(&self, title: &str) -> Option<&Section> {
+        find(&self.sections, title)
+    }
+
+    /// All the paragraphs of the document in order, without the headings.
+    pub fn paragraphs(&self) -> Vec<&Paragraph> {
+        let mut paragraphs = Vec::new();
+        collect(&self.blocks, &self.sections, &mut paragraphs);
+        paragraphs
+    }
+}
+
+impl Section {
+    /// The title as plain text.
+    pub fn title_text(&self) -> String {
+        self.title
+            .to_string()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Finds a subsection by its title, at any depth.
+    pub fn section(&self, title: &str) -> Option<&Section> {
+        find(&self.sections, title)
+    }
+
+    /// All the paragraphs of the section and its subsections in order, without the headings.
+    pub fn paragraphs(&self) -> Vec<&Paragraph> {
+        let mut paragraphs = Vec::new();
+        collect(&self.blocks, &self.sections, &mut paragraphs);
+        paragraphs
+    }
+}
+
+fn find<'a>(sections: &'a [Section], title: &str) -> Option<&'a Section> {
+    sections
+        .iter()
+        .find_map(|section| match section.title_text() == title {
+            true => Some(section),
+            false => section.section(title),
+        })
+}
+
+fn collect<'a>(blocks: &'a [Block], sections: &'a [Section], paragraphs: &mut Vec<&'a Paragraph>) {
+    for block in blocks {
+        match block {
+            Block::Paragraph(paragraph) => paragraphs.push(paragraph),
+            Block::Env(_, ast) => paragraphs.extend(ast.paragraphs()),
+            Block::Theorem(theorem) => paragraphs.extend(theorem.body.paragraphs()),
+            Block::List(list) => {
+                for item in &list.items {
+                    paragraphs.extend(item.body.paragraphs());
+                }
+            }
+            Block::Float(float) => {
+                for caption in &float.captions {
+                    paragraphs.extend(caption.text.paragraphs());
+                }
+                for cell in float.tables.iter().flat_map(Table::cells) {
+                    paragraphs.extend(cell.paragraphs());
+                }
+            }
+            Block::Table(table) => {
+                for cell in table.cells() {
+                    paragraphs.extend(cell.paragraphs());
+                }
+            }
+        }
+    }
+    for section in sections {
+        collect(&section.blocks, &section.sections, paragraphs);
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        write_blocks(f, &self.blocks, &self.sections, &mut first)
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        write_section(f, self, &mut first)
+    }
+}
+
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Block::Paragraph(paragraph) => write!(f, "{paragraph}"),
+            Block::Env(env, ast) => super::write_env(f, env, ast),
+            Block::Theorem(theorem) => write!(f, "{theorem}"),
+            Block::List(list) => write!(f, "{}", format!("{list}").trim_matches('\n')),
+            Block::Float(float) => write!(f, "{float}"),
+            Block::Table(table) => write!(f, "{table}"),
+        }
+    }
+}
+
+/// Writes blocks and sections separated by blank lines, like the paragraphs of an [`Ast`].
+fn write_blocks(
+    f: &mut fmt::Formatter<'_>,
+    blocks: &[Block],
+    sections: &[Section],
+    first: &mut bool,
+) -> fmt::Result {
+    for block in blocks {
+        separate(f, first)?;
+        write!(f, "{block}")?;
+    }
+    for section in sections {
+        write_section(f, section, first)?;
+    }
+    Ok(())
+}
+
+fn write_section(f: &mut fmt::Formatter<'_>, section: &Section, first: &mut bool) -> fmt::Result {
+    separate(f, first)?;
+    write!(f, "{} {}", "#".repeat(section.level.into()), section.title)?;
+    write_blocks(f, &section.blocks, &section.sections, first)
+}
+
+fn separate(f: &mut fmt::Formatter<'_>, first: &mut bool) -> fmt::Result {
+    match std::mem::replace(first, false) {
+        true => Ok(()),
+        false => write!(f, "\n\n"),
+    }
+}
+
+#[test]
+fn test_sections() {
+    use std::str::FromStr;
+    let s = r"Abstract.
+\section{Introduction} We start.
+
+\subsection{Aims} We aim.
+\begin{itemize}\item high\end{itemize}
+\section{End} Bye.";
+    let doc = crate::token::Document::from_str(s).unwrap();
+    let ast = super::convert(None, doc, super::Resolver::default());
+    let document = Document::from(ast);
+    assert!(document
+        .to_string()
+        .starts_with("Abstract.\n\n# Introduction\n\nWe start."));
+    assert_eq!(document.blocks.len(), 1);
+    assert_eq!(document.sections.len(), 2);
+    let introduction = document.section("Introduction").unwrap();
+    assert_eq!(introduction.sections[0].title_text(), "Aims");
+    assert!(matches!(
+        introduction.sections[0].blocks[1],
+        Block::List(..)
+    ));
+    let paragraphs = introduction.paragraphs();
+    let paragraphs = paragraphs
+        .iter()
+        .map(|p| {
+            p.to_string()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(paragraphs, ["We start.", "We aim.", "high"]);
+    assert_eq!(document.section("Aims"), Some(&introduction.sections[0]));
+}
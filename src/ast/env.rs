@@ -2,8 +2,7 @@ use super::Ast;
 
 pub fn write_env(f: &mut std::fmt::Formatter<'_>, env: &str, ast: &Ast) -> std::fmt::Result {
     match env {
-        "itemize" | "enumerate" => write!(f, "{ast}"),
-        _ if env.starts_with("align") | env.starts_with("equ") => writeln!(f, "\n%%%% MATH %%%%"),
+        _ if is_math(env) => writeln!(f, "\n%%%% MATH %%%%"),
         _ => {
             writeln!(f, "\n% ---------- \\begin: {env} ----------")?;
             write!(f, "{ast}")?;
@@ -11,3 +10,8 @@ pub fn write_env(f: &mut std::fmt::Formatter<'_>, env: &str, ast: &Ast) -> std::
         }
     }
 }
+
+/// Whether an environment is display math.
+pub fn is_math(env: &str) -> bool {
+    env.starts_with("align") | env.starts_with("equ")
+}
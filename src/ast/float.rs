@@ -0,0 +1,129 @@
+use std::fmt;
+
+use super::command::Command;
+use super::{Ast, Table, Word};
+
+/// A figure or a table, of which only the captions and the tables are kept.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Float {
+    pub env: String,
+    pub captions: Vec<Caption>,
+    /// The tables in the body, written after the captions.
+    pub tables: Vec<Table>,
+}
+
+/// A `\caption`, e.g. `Figure 3: A plot.`
+#[derive(Debug, PartialEq, Eq)]
+pub struct Caption {
+    /// The name and the number, e.g. `Figure 3`, or `None` for `\caption*`.
+    pub name: Option<String>,
+    pub text: Ast,
+}
+
+impl Float {
+    /// Takes the captions and the tables out of the converted body of a float,
+    /// dropping the rest such as graphics.
+    pub fn new(env: String, body: Ast) -> Self {
+        let mut float = Float {
+            env,
+            captions: Vec::new(),
+            tables: Vec::new(),
+        };
+        float.take(body);
+        float
+    }
+
+    /// Whether nothing of the body is kept.
+    pub fn is_empty(&self) -> bool {
+        self.captions.is_empty() && self.tables.is_empty()
+    }
+
+    fn take(&mut self, ast: Ast) {
+        for word in ast.0.into_iter().flat_map(|paragraph| paragraph.0) {
+            match word {
+                Word::Command(Command::Caption(caption)) => self.captions.push(caption),
+                Word::Table(table) => self.tables.push(table),
+                Word::Lines(ast) | Word::Env(_, ast) => self.take(ast),
+                Word::List(list) => {
+                    for item in list.items {
+                        self.take(item.body);
+                    }
+                }
+                Word::Float(float) => {
+                    self.captions.extend(float.captions);
+                    self.tables.extend(float.tables);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl fmt::Display for Caption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name}: {}", self.text),
+            None => write!(f, "{}", self.text),
+        }
+    }
+}
+
+impl fmt::Display for Float {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let captions = self
+            .captions
+            .iter()
+            .map(|caption| caption as &dyn fmt::Display);
+        let tables = self.tables.iter().map(|table| table as &dyn fmt::Display);
+        let mut iter = captions.chain(tables).peekable();
+        while let Some(block) = iter.next() {
+            write!(f, "{block}")?;
+            if iter.peek().is_some() {
+                write!(f, "\n\n")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_floats() {
+    use std::str::FromStr;
+    let s = r"\begin{figure}[t]\centering
+\includegraphics[width=\linewidth]{plot.pdf}
+\caption[Short]{A plot of the data.}\label{f}
+\end{figure}
+\begin{table*}\caption{Results.}\label{t}\begin{tabular}{ll} a & b \\ \hline \end{tabular}\end{table*}
+\begin{figure}\caption{First.}\caption*{Unnumbered.}\caption{Second.}\label{s}\end{figure}
+See \cref{f,t,s}.";
+    let convert = |tables: bool| {
+        let doc = crate::token::Document::from_str(s).unwrap();
+        let mut ast = super::convert(None, doc, super::Resolver::default());
+        if !tables {
+            super::drop_tables(&mut ast);
+        }
+        crate::arrange::normalize(&mut ast, &Default::default());
+        format!("{ast}")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    assert_eq!(
+        convert(false),
+        "Figure 1: A plot of the data. Table 1: Results. Figure 2: First. Unnumbered. \
+        Figure 3: Second. See figures 1 and 3 and table 1."
+    );
+    // the cells of a table in a float go after its caption
+    assert_eq!(
+        convert(true),
+        "Figure 1: A plot of the data. Table 1: Results. a b Figure 2: First. Unnumbered. \
+        Figure 3: Second. See figures 1 and 3 and table 1."
+    );
+    let doc = crate::token::Document::from_str(
+        r"\begin{table}\centering\begin{tabular}{l} Cell \end{tabular}\end{table}",
+    )
+    .unwrap();
+    let ast = super::convert(None, doc, super::Resolver::default());
+    let document = super::Document::from(ast);
+    assert_eq!(format!("{document}").trim(), "Cell");
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::command::Command;
+use super::{Ast, Paragraph, Text, Word};
+
+/// A `\footnote` with its number.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Footnote {
+    pub number: String,
+    pub text: Ast,
+}
+
+impl Footnote {
+    /// The mark left in the text, e.g. `[^1]`.
+    pub fn mark(&self) -> String {
+        format!("[^{}]", self.number)
+    }
+}
+
+impl fmt::Display for Footnote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mark())
+    }
+}
+
+/// Where [`place_footnotes`] puts the text of the footnotes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Placement {
+    /// In paragraphs after the paragraph with their marks.
+    After,
+    /// In a section of notes at the end of the document.
+    End,
+    /// Nowhere, without marks.
+    Drop,
+}
+
+impl FromStr for Placement {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "after" => Ok(Placement::After),
+            "end" => Ok(Placement::End),
+            "drop" => Ok(Placement::Drop),
+            _ => Err(format!("unknown footnote placement: {s}")),
+        }
+    }
+}
+
+/// Moves the text of the footnotes out of the sentences, leaving a mark such as `[^1]` in them.
+pub fn place_footnotes(ast: &mut Ast, placement: Placement) {
+    let mut endnotes = Vec::new();
+    for paragraph in std::mem::take(&mut ast.0) {
+        let mut paragraph = Ast(vec![paragraph]);
+        let mut footnotes = Vec::new();
+        take_footnotes(&mut paragraph, placement != Placement::Drop, &mut footnotes);
+        ast.0.extend(paragraph.0);
+        match placement {
+            Placement::After => ast.0.extend(footnotes.into_iter().flat_map(note)),
+            Placement::End => endnotes.extend(footnotes),
+            Placement::Drop => {}
+        }
+    }
+    if !endnotes.is_empty() {
+        let title = Ast(vec![Paragraph(vec![Word::Text(Text::new("Notes"))])]);
+        let depth = top_depth(ast);
+        ast.push(Paragraph(vec![Word::Command(Command::Section(
+            depth, title,
+        ))]));
+        ast.0.extend(endnotes.into_iter().flat_map(note));
+    }
+}
+
+/// The depth of the outermost sections of `ast`, below a first heading such as the title
+/// if all the others are deeper.
+fn top_depth(ast: &Ast) -> u8 {
+    let depths = ast
+        .paragraphs()
+        .iter()
+        .filter_map(|paragraph| match paragraph.words() {
+            [Word::Command(Command::Section(depth, _))] => Some(*depth),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let depths = match depths.split_first() {
+        Some((first, rest)) if !rest.is_empty() && rest.iter().all(|depth| depth > first) => rest,
+        _ => &depths,
+    };
+    depths.iter().copied().min().unwrap_or(1)
+}
+
+/// The paragraphs of a footnote, the first one starting with its mark, e.g. `[^1]: Text.`
+fn note(footnote: Footnote) -> Vec<Paragraph> {
+    let mark = Word::Text(Text::new(format!("{}:", footnote.mark())));
+    let mut paragraphs = footnote.text.0;
+    match paragraphs.first_mut() {
+        Some(first) => first.0.insert(0, mark),
+        None => paragraphs.push(Paragraph(vec![mark])),
+    }
+    paragraphs
+}
+
+/// Takes the footnotes out of `ast`, replacing them with their marks if `mark` is set.
+fn take_footnotes(ast: &mut Ast, mark: bool, footnotes: &mut Vec<Footnote>) {
+    for paragraph in ast.paragraphs_mut() {
+        let words = paragraph.words_mut();
+        for word in std::mem::take(words) {
+            match word {
+                Word::Command(Command::Footnote(mut footnote)) => {
+                    // the footnotes in a footnote go after it
+                    let mut nested = Vec::new();
+                    take_footnotes(&mut footnote.text, mark, &mut nested);
+                    if mark {
+                        words.push(Word::Text(Text {
+                            glued: true,
+                            ..Text::new(footnote.mark())
+                        }));
+                    }
+                    footnotes.push(footnote);
+                    footnotes.extend(nested);
+                }
+                word => words.push(word),
+            }
+        }
+        for word in words.iter_mut() {
+            match word {
+                Word::Lines(ast) | Word::Env(_, ast) | Word::Command(Command::Section(_, ast)) => {
+                    take_footnotes(ast, mark, footnotes)
+                }
+                Word::Command(Command::Caption(caption)) => {
+                    take_footnotes(&mut caption.text, mark, footnotes)
+                }
+                Word::Theorem(theorem) => take_footnotes(&mut theorem.body, mark, footnotes),
+                Word::Float(float) => {
+                    for caption in &mut float.captions {
+                        take_footnotes(&mut caption.text, mark, footnotes);
+                    }
+                    for cell in float
+                        .tables
+                        .iter_mut()
+                        .flat_map(|table| &mut table.rows)
+                        .flatten()
+                    {
+                        take_footnotes(cell, mark, footnotes);
+                    }
+                }
+                Word::List(list) => {
+                    for item in &mut list.items {
+                        take_footnotes(&mut item.body, mark, footnotes);
+                    }
+                }
+                Word::Table(table) => {
+                    for cell in table.rows.iter_mut().flatten() {
+                        take_footnotes(cell, mark, footnotes);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn test_footnotes() {
+    use std::str::FromStr;
+    let s = r"First\footnote{A note.} word \emph{and\footnote[7]{Seven.}}.
+
+Second\footnote{Last.\label{n}}, see note~\ref{n}.";
+    let convert = |placement| {
+        let doc = crate::token::Document::from_str(s).unwrap();
+        let mut ast = super::convert(None, doc, super::Resolver::default());
+        place_footnotes(&mut ast, placement);
+        crate::arrange::normalize(&mut ast, &Default::default());
+        format!("{ast}")
+    };
+    assert_eq!(
+        convert(Placement::After),
+        "First[^1] word and[^7].\n\n[^1]: A note.\n\n[^7]: Seven.\n\n\
+        Second[^2], see note 2.\n\n[^2]: Last."
+    );
+    assert_eq!(
+        convert(Placement::End),
+        "First[^1] word and[^7].\n\nSecond[^2], see note 2.\n\n# Notes\n\n\
+        [^1]: A note.\n\n[^7]: Seven.\n\n[^2]: Last."
+    );
+    assert_eq!(
+        convert(Placement::Drop),
+        "First word and.\n\nSecond, see note 2."
+    );
+    let preamble = crate::token::Document::from_str(r"\title{Title}").unwrap();
+    let s = r"\maketitle \section{A} We agree.\footnote{Mostly\footnote{Inner.}.} Next.";
+    let doc = crate::token::Document::from_str(s).unwrap();
+    let mut ast = super::convert(Some(preamble), doc, super::Resolver::default());
+    place_footnotes(&mut ast, Placement::End);
+    crate::arrange::normalize(&mut ast, &Default::default());
+    // the mark stays in the sentence before it
+    let sentences = ast.paragraphs()[2]
+        .sentences()
+        .iter()
+        .map(|sentence| sentence.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(sentences, ["We agree.[^1]", "Next."]);
+    // a footnote in a footnote, and the notes as a section below the title
+    assert_eq!(
+        format!("{ast}"),
+        "# Title\n\n## A\n\nWe agree.[^1] Next.\n\n## Notes\n\n[^1]: Mostly[^2].\n\n[^2]: Inner."
+    );
+}
@@ -0,0 +1,112 @@
+use super::command::Command;
+use super::{Ast, Context, Paragraph, Text, Word};
+
+/// Environments of the front matter written as sections, with their headings.
+const SECTIONS: [(&str, &str); 3] = [
+    ("abstract", "Abstract"),
+    ("keywords", "Keywords"),
+    ("IEEEkeywords", "Keywords"),
+];
+
+/// The heading of an environment written as a section, e.g. `Abstract`.
+pub(super) fn section_heading(env: &str) -> Option<&'static str> {
+    SECTIONS
+        .iter()
+        .find(|(section, _)| *section == env)
+        .map(|(_, heading)| *heading)
+}
+
+/// Whether an environment is a title page written by hand, whose paragraphs stand in the document
+/// as they are. Unlike beamer's `\titlepage` it does not make the title of `\maketitle`.
+pub(super) fn is_title_page(env: &str) -> bool {
+    env == "titlepage"
+}
+
+/// The paragraphs of a section of the front matter: its heading at the depth
+/// of the outermost sections, then its body.
+pub(super) fn front_section(heading: &str, body: Ast, ctx: &Context) -> Vec<Paragraph> {
+    let title = Ast(vec![Paragraph(vec![Word::Text(Text::new(heading))])]);
+    let mut paragraphs = vec![Paragraph(vec![Word::Command(Command::Section(
+        ctx.top_depth(),
+        title,
+    ))])];
+    paragraphs.extend(body.0);
+    paragraphs
+}
+
+/// The paragraphs of `\maketitle`: the title as the top heading and the author block.
+pub(super) fn make_title(ctx: &mut Context) -> Vec<Paragraph> {
+    let (title, authors) = ctx.make_title();
+    let mut paragraphs = Vec::new();
+    if let Some(title) = title {
+        paragraphs.push(Paragraph(vec![Word::Command(Command::Section(1, title))]));
+    }
+    if let Some(authors) = authors {
+        paragraphs.push(Paragraph(vec![Word::Command(Command::Authors(authors))]));
+    }
+    paragraphs
+}
+
+/// Drops the author block of `\maketitle` from `ast`.
+pub fn drop_authors(ast: &mut Ast) {
+    ast.0
+        .retain(|paragraph| !matches!(paragraph.0[..], [Word::Command(Command::Authors(_))]));
+}
+
+#[test]
+fn test_front_matter() {
+    use std::str::FromStr;
+    let preamble = r"\documentclass{article}
+\title{On Things\thanks{Funded.}}
+\author{Alice \and Bob}
+\date{\today}";
+    let body = r"\maketitle
+\begin{abstract}
+We study things.
+\end{abstract}
+\keywords{things, stuff}
+\section{Introduction}
+Text.";
+    let convert = |authors: bool| {
+        let preamble = crate::token::Document::from_str(preamble).unwrap();
+        let body = crate::token::Document::from_str(body).unwrap();
+        let mut ast = super::convert(Some(preamble), body, super::Resolver::default());
+        if !authors {
+            drop_authors(&mut ast);
+        }
+        crate::arrange::normalize(&mut ast, &Default::default());
+        let document = super::Document::from(ast);
+        let sections = document.sections[0]
+            .sections
+            .iter()
+            .map(|section| section.title_text())
+            .collect::<Vec<_>>();
+        assert_eq!(sections, ["Abstract", "Keywords", "Introduction"]);
+        format!("{document}")
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        convert(true),
+        [
+            "# On Things",
+            "Alice and Bob",
+            "## Abstract",
+            "We study things.",
+            "## Keywords",
+            "things, stuff",
+            "## Introduction",
+            "Text."
+        ]
+    );
+    assert_eq!(convert(false)[1], "## Abstract");
+    // a title page written by hand is text like any other, and `\titlepage` is beamer's
+    let preamble = crate::token::Document::from_str(preamble).unwrap();
+    let body = r"\titlepage \begin{titlepage}\centering On Things\end{titlepage} Text.";
+    let body = crate::token::Document::from_str(body).unwrap();
+    let ast = super::convert(Some(preamble), body, super::Resolver::default());
+    assert_eq!(format!("{ast}"), "On Things\n\nText.");
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+/// What a `\label` refers to.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Target {
+    /// The type of the target as cleveref calls it: a counter such as `section`,
+    /// or the environment of a theorem such as `lemma`. Empty when unknown.
+    pub kind: String,
+    /// The number printed by `\ref`, e.g. `2.1`.
+    pub number: String,
+    /// The page, known only from an `.aux` file.
+    pub page: Option<String>,
+}
+
+/// The labels of a document, keyed by their label keys.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct Labels(HashMap<String, Target>);
+
+impl Labels {
+    pub fn insert(&mut self, key: String, target: Target) {
+        if self.0.insert(key.clone(), target).is_some() {
+            log::warn!("label `{key}` multiply defined");
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Target> {
+        self.0.get(key)
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt;
+
+use super::counter::alph;
+use super::token_to_ast::{take_optional, token_to_ast};
+use super::{Ast, Context, Paragraph, Text, Word};
+use crate::token;
+
+/// An `itemize`, `enumerate` or `description` list.
+#[derive(Debug, PartialEq, Eq)]
+pub struct List {
+    pub kind: ListKind,
+    /// The nesting depth, 1 for a list which is not in another one.
+    pub depth: usize,
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ListKind {
+    Itemize,
+    Enumerate,
+    Description,
+}
+
+/// An item of a list with its label: a bullet, a number such as `(a)` or the term of `\item[term]`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Item {
+    pub label: Ast,
+    pub body: Ast,
+}
+
+impl List {
+    /// The indentation of the items.
+    pub fn indent(&self) -> String {
+        "  ".repeat(self.depth.saturating_sub(1))
+    }
+
+    /// The label of an item as written before its body, e.g. `-`, `2.` or `Term:`.
+    pub fn marker(&self, item: &Item) -> String {
+        let label = format!("{}", item.label);
+        let label = label.split_whitespace().collect::<Vec<_>>().join(" ");
+        match self.kind {
+            _ if label.is_empty() => "-".to_string(),
+            ListKind::Description => format!("{}:", label.trim_end_matches(':')),
+            _ => label,
+        }
+    }
+}
+
+/// How an `enumerate` list numbers its items, as `\arabic` or `\alph` print a counter.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Numbering {
+    Arabic,
+    Alph,
+    CapitalAlph,
+    Roman,
+    CapitalRoman,
+}
+
+/// The counters of the nested `enumerate` lists and their default labels, as in LaTeX.
+const ENUMERATE: [(&str, &str); 4] = [
+    ("enumi", r"\arabic*."),
+    ("enumii", r"(\alph*)"),
+    ("enumiii", r"\roman*."),
+    ("enumiv", r"\Alph*."),
+];
+
+/// Converts the body of a list environment, the environment being entered.
+/// Gives the body back for other environments.
+pub(super) fn token_to_list(
+    env: &str,
+    mut doc: token::Document,
+    ctx: &mut Context,
+) -> Result<List, token::Document> {
+    let kind = match env {
+        "itemize" => ListKind::Itemize,
+        "enumerate" => ListKind::Enumerate,
+        "description" => ListKind::Description,
+        _ => return Err(doc),
+    };
+    let depth = ctx.list_depth();
+    let (counter, default_label) = ENUMERATE[(ctx.enumerate_depth().max(1) - 1).min(3)];
+    // enumitem's `label=` and `start=`
+    let options = take_optional(&mut doc).map(raw).unwrap_or_default();
+    let option = |key: &str| {
+        options
+            .split(',')
+            .find_map(|option| option.trim().strip_prefix(key)?.trim().strip_prefix('='))
+            .map(|value| value.trim().trim_matches(['{', '}']).to_string())
+    };
+    let template = option("label").unwrap_or_else(|| default_label.to_string());
+    let start = option("start")
+        .and_then(|start| start.parse().ok())
+        .unwrap_or(1);
+
+    let mut items = Vec::new();
+    let mut number = start;
+    for (term, body) in split_items(doc, ctx) {
+        let label = match (term, kind) {
+            (Some(term), _) => token_to_ast(term, ctx),
+            (None, ListKind::Enumerate) => {
+                let label = enumerate_label(&template, number);
+                ctx.enter_item(counter, label.trim_end_matches('.'));
+                number += 1;
+                Ast(vec![Paragraph(vec![Word::Text(Text::new(label))])])
+            }
+            (None, ListKind::Itemize) => Ast(vec![Paragraph(vec![Word::Text(Text::new("-"))])]),
+            (None, ListKind::Description) => Ast::new(),
+        };
+        let body = token_to_ast(body, ctx);
+        items.push(Item { label, body });
+    }
+    Ok(List { kind, depth, items })
+}
+
+/// Splits the body of a list at `\item`, with the optional terms of the items.
+/// What comes before the first item is converted for its definitions and dropped.
+fn split_items(
+    doc: token::Document,
+    ctx: &mut Context,
+) -> Vec<(Option<token::Document>, token::Document)> {
+    let mut items = Vec::new();
+    let mut before = token::Document::default();
+    let mut words = doc.words.into_iter().peekable();
+    while let Some(word) = words.next() {
+        if word != token::Word::Command(token::Command::Item) {
+            match items.last_mut() {
+                Some((_, body)) => body,
+                None => &mut before,
+            }
+            .words
+            .push(word);
+            continue;
+        }
+        let term = match words.next_if(|w| matches!(w, token::Word::Optional(_))) {
+            Some(token::Word::Optional(term)) => Some(term),
+            _ => None,
+        };
+        items.push((term, token::Document::default()));
+    }
+    token_to_ast(before, ctx);
+    items
+}
+
+/// Reads an optional argument as written, e.g. `label=(\roman*)`.
+fn raw(doc: token::Document) -> String {
+    let mut s = String::new();
+    let mut words = doc.words.into_iter().peekable();
+    while let Some(word) = words.next() {
+        match word {
+            token::Word::Text(text, _) => {
+                s += &text;
+                if matches!(words.peek(), Some(token::Word::Text(..))) {
+                    s.push(' ');
+                }
+            }
+            token::Word::Command(token::Command::Unknown(name)) => s += &format!(r"\{name}"),
+            token::Word::Command(token::Command::Known(sig)) => s += &format!(r"\{}", sig.name),
+            token::Word::Lines(doc) => s += &format!("{{{}}}", raw(doc)),
+            _ => {}
+        }
+    }
+    s
+}
+
+/// The label of the `n`-th item by a template such as `(\alph*)`.
+fn enumerate_label(template: &str, n: u32) -> String {
+    let numberings = [
+        (r"\arabic*", Numbering::Arabic),
+        (r"\alph*", Numbering::Alph),
+        (r"\Alph*", Numbering::CapitalAlph),
+        (r"\roman*", Numbering::Roman),
+        (r"\Roman*", Numbering::CapitalRoman),
+    ];
+    let mut label = template.replace(['{', '}'], "");
+    for (command, numbering) in numberings {
+        label = label.replace(command, &numbering.format(n));
+    }
+    label
+}
+
+impl Numbering {
+    fn format(self, n: u32) -> String {
+        match self {
+            Numbering::Arabic => n.to_string(),
+            Numbering::Alph => Numbering::CapitalAlph.format(n).to_lowercase(),
+            Numbering::CapitalAlph => alph(n),
+            Numbering::Roman => Numbering::CapitalRoman.format(n).to_lowercase(),
+            Numbering::CapitalRoman => {
+                const NUMERALS: [(u32, &str); 13] = [
+                    (1000, "M"),
+                    (900, "CM"),
+                    (500, "D"),
+                    (400, "CD"),
+                    (100, "C"),
+                    (90, "XC"),
+                    (50, "L"),
+                    (40, "XL"),
+                    (10, "X"),
+                    (9, "IX"),
+                    (5, "V"),
+                    (4, "IV"),
+                    (1, "I"),
+                ];
+                let mut n = n;
+                let mut s = String::new();
+                for (value, numeral) in NUMERALS {
+                    while n >= value {
+                        s += numeral;
+                        n -= value;
+                    }
+                }
+                s
+            }
+        }
+    }
+}
+
+impl fmt::Display for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indent = self.indent();
+        for item in &self.items {
+            write!(f, "\n{indent}{}", self.marker(item))?;
+            // the paragraphs after the first one are indented under the label
+            for (i, paragraph) in item.body.paragraphs().iter().enumerate() {
+                match i {
+                    0 => write!(f, " {paragraph}")?,
+                    _ => write!(f, "\n\n{indent}  {paragraph}")?,
+                }
+            }
+        }
+        // the words after a list go on a new line, unless it ends an item
+        match self.depth {
+            1 => writeln!(f),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_lists() {
+    use std::str::FromStr;
+    let s = r"\begin{enumerate}[label=(\roman*), start=3]
+\item First\label{i}
+  \begin{itemize} \item Inner \begin{enumerate} \item Deep \end{enumerate} \end{itemize}
+\item Second, see \ref{i}.
+\end{enumerate}
+\begin{description} \item[Term:] Meaning. \item[Other] Two. \end{description}";
+    let doc = token::Document::from_str(s).unwrap();
+    let ast = super::convert(None, doc, super::Resolver::default());
+    let lines = format!("{ast}")
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "(iii) First",
+            "  - Inner",
+            "    (a) Deep",
+            "(iv) Second, see (iii) .",
+            "Term: Meaning.",
+            "Other: Two."
+        ]
+    );
+    assert_eq!(enumerate_label(r"\Roman*.", 14), "XIV.");
+}
@@ -0,0 +1,235 @@
+use super::Context;
+use crate::token::RefStyle;
+
+/// The number written for a target which is unknown to the resolver, as LaTeX does.
+pub(super) const UNKNOWN_NUMBER: &str = "??";
+
+/// A resolved target of a reference.
+struct Resolved {
+    /// The name of the type, empty for an unknown target.
+    name: String,
+    kind: String,
+    /// The number, empty for a target whose number is not known.
+    number: String,
+    page: String,
+}
+
+impl Resolved {
+    fn new(key: &str, ctx: &Context) -> Self {
+        let Some(target) = ctx.reference(key) else {
+            // a type is not guessed for an unknown target
+            return Resolved {
+                name: String::new(),
+                kind: String::new(),
+                number: UNKNOWN_NUMBER.to_string(),
+                page: UNKNOWN_NUMBER.to_string(),
+            };
+        };
+        Resolved {
+            // the `.aux` file may not give the type, which the key then suggests
+            name: ctx
+                .kind_name(&target.kind)
+                .unwrap_or_else(|| make_ref_name(key.to_string())),
+            kind: target.kind.clone(),
+            number: target.number.clone(),
+            page: target
+                .page
+                .clone()
+                .unwrap_or_else(|| UNKNOWN_NUMBER.to_string()),
+        }
+    }
+
+    /// The number alone, as `\ref` prints it.
+    fn plain_number(&self) -> &str {
+        match &*self.number {
+            "" => UNKNOWN_NUMBER,
+            number => number,
+        }
+    }
+
+    /// The number as cleveref prints it: equations are in parentheses.
+    fn clever_number(&self) -> String {
+        match &*self.kind {
+            "equation" => format!("({})", self.number),
+            _ => self.number.clone(),
+        }
+    }
+}
+
+/// Renders a reference to comma-separated `keys`.
+/// Only cleveref's commands accept several keys.
+pub(super) fn reference(style: RefStyle, keys: &str, ctx: &Context) -> String {
+    use RefStyle::*;
+    match style {
+        Number => Resolved::new(keys, ctx).plain_number().to_string(),
+        Equation => format!("({})", Resolved::new(keys, ctx).plain_number()),
+        Page => Resolved::new(keys, ctx).page,
+        Auto => {
+            let target = Resolved::new(keys, ctx);
+            named(&target.name, &target.number)
+        }
+        Name | CapitalName => {
+            let targets = keys.split(',').map(|key| Resolved::new(key, ctx));
+            clever(style, targets.collect(), ctx)
+        }
+    }
+}
+
+/// Renders `\crefrange{first}{last}`, e.g. `sections 2 to 5`.
+pub(super) fn reference_range(style: RefStyle, first: &str, last: &str, ctx: &Context) -> String {
+    let (first, last) = (Resolved::new(first, ctx), Resolved::new(last, ctx));
+    let name = clever_name(style, plural(&first.name), ctx);
+    let numbers = format!("{} to {}", first.clever_number(), last.clever_number());
+    named(&name, &numbers)
+}
+
+/// Writes `numbers` after the name of their type, if they and the name are known.
+fn named(name: &str, numbers: &str) -> String {
+    match (name, numbers) {
+        ("", _) => numbers.to_string(),
+        (_, "") => name.to_string(),
+        _ => format!("{name} {numbers}"),
+    }
+}
+
+/// Renders references as cleveref does: grouped by type with plural names,
+/// and with consecutive numbers compressed into ranges,
+/// e.g. `theorems 1 to 3 and lemma 4`.
+fn clever(style: RefStyle, targets: Vec<Resolved>, ctx: &Context) -> String {
+    let mut groups: Vec<(String, Vec<Resolved>)> = Vec::new();
+    for target in targets {
+        match groups.iter_mut().find(|(name, _)| *name == target.name) {
+            Some((_, group)) => group.push(target),
+            None => groups.push((target.name.clone(), vec![target])),
+        }
+    }
+    let groups = groups
+        .into_iter()
+        .map(|(name, mut group)| {
+            let name = match group.len() {
+                1 => name,
+                _ => plural(&name),
+            };
+            group.sort_by_key(|target| number_key(&target.number));
+            let mut numbers = compress(&group);
+            numbers.retain(|number| !number.is_empty());
+            named(&clever_name(style, name, ctx), &join_and(numbers))
+        })
+        .collect();
+    join_and(groups)
+}
+
+/// `\cref` names are lowercase unless cleveref is loaded with `capitalise`.
+fn clever_name(style: RefStyle, name: String, ctx: &Context) -> String {
+    match style == RefStyle::Name && !ctx.has_package_option("cleveref", "capitalise") {
+        true => name.to_lowercase(),
+        false => name,
+    }
+}
+
+/// Orders numbers like `2.10` after `2.9`.
+fn number_key(number: &str) -> Vec<Option<u32>> {
+    number.split('.').map(|n| n.parse().ok()).collect()
+}
+
+/// Compresses three or more consecutive numbers into a range.
+fn compress(targets: &[Resolved]) -> Vec<String> {
+    let mut numbers = Vec::new();
+    let mut i = 0;
+    while i < targets.len() {
+        let mut j = i;
+        while j + 1 < targets.len() && follows(&targets[j].number, &targets[j + 1].number) {
+            j += 1;
+        }
+        if j >= i + 2 {
+            let (first, last) = (&targets[i], &targets[j]);
+            numbers.push(format!(
+                "{} to {}",
+                first.clever_number(),
+                last.clever_number()
+            ));
+        } else {
+            numbers.extend(targets[i..=j].iter().map(Resolved::clever_number));
+        }
+        i = j + 1;
+    }
+    numbers
+}
+
+/// Whether `next` is the number right after `number`, e.g. `2.4` after `2.3`.
+fn follows(number: &str, next: &str) -> bool {
+    let (key, next_key) = (number_key(number), number_key(next));
+    match (key.split_last(), next_key.split_last()) {
+        (Some((Some(n), prefix)), Some((Some(m), next_prefix))) => {
+            prefix == next_prefix && n + 1 == *m
+        }
+        _ => false,
+    }
+}
+
+/// Joins items as `a`, `a and b` or `a, b and c`.
+pub(super) fn join_and(mut items: Vec<String>) -> String {
+    match items.pop() {
+        None => String::new(),
+        Some(last) if items.is_empty() => last,
+        Some(last) => format!("{} and {last}", items.join(", ")),
+    }
+}
+
+fn plural(name: &str) -> String {
+    let vowels = ['a', 'e', 'i', 'o', 'u'];
+    if name.is_empty() {
+        String::new()
+    } else if let Some(stem) = name
+        .strip_suffix('y')
+        .filter(|stem| !stem.ends_with(vowels))
+    {
+        format!("{stem}ies")
+    } else if let Some(stem) = name.strip_suffix("ix") {
+        format!("{stem}ices")
+    } else if let Some(stem) = name.strip_suffix("is") {
+        format!("{stem}es")
+    } else if name.ends_with(['s', 'x']) || name.ends_with("ch") || name.ends_with("sh") {
+        format!("{name}es")
+    } else {
+        format!("{name}s")
+    }
+}
+
+fn make_ref_name(s: String) -> String {
+    let mut s = s
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<String>()
+        .to_lowercase();
+    s.truncate(3);
+    let new_name = match &*s {
+        "sec" => "Section",
+        "sub" => "Section",
+        "thm" | "the" => "Theorem",
+        "lem" => "Lemma",
+        "pro" | "prp" => "Proposition",
+        "cor" => "Corollary",
+        "def" => "Definition",
+        "fig" => "Figure",
+        "tab" => "Table",
+        "cha" => "Chapter",
+        "app" => "Appendix",
+        c if c.strip_prefix("eq").is_some() => "Equation",
+        c if c.strip_prefix("eg").is_some() => "Example",
+        c if c.strip_prefix("ex").is_some() => "Example",
+        c if c.strip_prefix("ax").is_some() => "Axiom",
+        "rem" | "rmk" => "Remark",
+        _ => "Theorem",
+    };
+    new_name.to_owned()
+}
+
+#[test]
+fn test_plural() {
+    assert_eq!(plural("Theorem"), "Theorems");
+    assert_eq!(plural("Corollary"), "Corollaries");
+    assert_eq!(plural("Appendix"), "Appendices");
+    assert_eq!(plural("Hypothesis"), "Hypotheses");
+    assert_eq!(plural("Key"), "Keys");
+}
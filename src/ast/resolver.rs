@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use super::label::{Labels, Target};
+use crate::bib::{self, Database};
+
+/// Resolves references and citations for the conversion.
+/// The `.aux` file of a compiled document gives exactly what the PDF shows;
+/// the labels collected by the previous pass are the fallback.
+/// Citations also use the `.bib` files of the document.
+#[derive(Debug, Default)]
+pub struct Resolver {
+    /// `\newlabel` entries of the `.aux` file.
+    aux: HashMap<String, Target>,
+    /// `\bibcite` entries of the `.aux` file.
+    bibcites: HashMap<String, Citation>,
+    /// Labels collected by the previous pass.
+    labels: Labels,
+    /// The directory of the document, where `.bib` files are looked up.
+    dir: PathBuf,
+    bib: Database,
+    /// Keys in the order of their first citation in the previous pass.
+    cited: Vec<String>,
+}
+
+/// A citation label written by BibTeX, e.g. `3`, or `Smith et al.`, `2020` with natbib.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Citation {
+    pub label: String,
+    pub year: Option<String>,
+    pub author: Option<String>,
+}
+
+impl Resolver {
+    /// Reads the `\newlabel` and `\bibcite` entries of an `.aux` file.
+    pub fn from_aux(s: &str) -> Self {
+        let mut resolver = Resolver::default();
+        let mut kinds = HashMap::new();
+        for line in s.lines() {
+            if let Some(rest) = line.strip_prefix("\\newlabel") {
+                let [key, value] = brace_groups(rest)[..] else {
+                    continue;
+                };
+                let fields = brace_groups(value);
+                if let Some(key) = key.strip_suffix("@cref") {
+                    // cleveref: {[type][counter value][...]number}{[...]page}
+                    if let Some(kind) = fields.first().and_then(|f| bracket_group(f)) {
+                        kinds.insert(key.to_string(), kind.to_string());
+                    }
+                    continue;
+                }
+                let Some(number) = fields.first() else {
+                    continue;
+                };
+                // hyperref: {number}{page}{title}{type.anchor}{}
+                let kind = fields
+                    .get(3)
+                    .and_then(|anchor| anchor.split('.').next())
+                    .filter(|kind| !kind.is_empty())
+                    .unwrap_or_default();
+                let target = Target {
+                    kind: kind.to_string(),
+                    number: clean(number),
+                    page: fields.get(1).map(|page| clean(page)),
+                };
+                resolver.aux.insert(key.to_string(), target);
+            } else if let Some(rest) = line.strip_prefix("\\bibcite") {
+                let [key, value] = brace_groups(rest)[..] else {
+                    continue;
+                };
+                let fields = brace_groups(value);
+                let citation = match fields[..] {
+                    // natbib: {{number}{year}{{short authors}}{{long authors}}}
+                    [label, year, author, ..] => Citation {
+                        label: clean(label),
+                        year: Some(clean(year)),
+                        author: Some(clean(author)),
+                    },
+                    _ => Citation {
+                        label: clean(value),
+                        year: None,
+                        author: None,
+                    },
+                };
+                resolver.bibcites.insert(key.to_string(), citation);
+            }
+        }
+        for (key, kind) in kinds {
+            if let Some(target) = resolver.aux.get_mut(&key) {
+                target.kind = kind;
+            }
+        }
+        resolver
+    }
+
+    /// Adds the labels collected by a pass over the document.
+    pub fn with_labels(self, labels: Labels) -> Self {
+        Resolver { labels, ..self }
+    }
+
+    /// Adds the keys cited by a pass over the document.
+    pub fn with_cited(self, cited: Vec<String>) -> Self {
+        Resolver { cited, ..self }
+    }
+
+    /// Looks up `.bib` files in `dir` instead of the working directory.
+    pub fn in_dir(self, dir: PathBuf) -> Self {
+        Resolver { dir, ..self }
+    }
+
+    /// Reads `.bib` files given as by `\bibliography`, where the extension may be omitted.
+    pub fn load_bibliographies(&mut self, names: &[String]) {
+        for name in names {
+            let mut path = self.dir.join(name);
+            if !name.ends_with(".bib") {
+                path.as_mut_os_string().push(".bib");
+            }
+            match read_to_string(&path) {
+                Ok(s) => self.bib.extend(Database::parse(&s).into_entries()),
+                Err(err) => log::warn!("failed to load {}; {err}", path.to_string_lossy()),
+            }
+        }
+    }
+
+    pub fn reference(&self, key: &str) -> Option<&Target> {
+        self.aux.get(key).or_else(|| self.labels.get(key))
+    }
+
+    pub fn citation(&self, key: &str) -> Option<&Citation> {
+        self.bibcites.get(key)
+    }
+
+    pub fn bib_entry(&self, key: &str) -> Option<&bib::Entry> {
+        self.bib.get(key)
+    }
+
+    /// The number of a work in the order of citation, as with unsorted numeric styles.
+    pub fn citation_number(&self, key: &str) -> Option<usize> {
+        self.cited
+            .iter()
+            .position(|cited| cited == key)
+            .map(|i| i + 1)
+    }
+}
+
+/// Splits the leading brace groups of `s`: `{a}{b{c}}d` gives `a` and `b{c}`.
+fn brace_groups(s: &str) -> Vec<&str> {
+    let mut groups = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, ch) in s.char_indices() {
+        match ch {
+            '{' => {
+                if depth == 0 {
+                    start = i + 1;
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    groups.push(&s[start..i]);
+                }
+            }
+            _ if depth == 0 && !ch.is_whitespace() => break,
+            _ => {}
+        }
+    }
+    groups
+}
+
+/// The content of the leading `[...]` of `s`.
+fn bracket_group(s: &str) -> Option<&str> {
+    let s = s.strip_prefix('[')?;
+    s.find(']').map(|end| &s[..end])
+}
+
+/// Strips the TeX markup that `.aux` files put around numbers and names.
+fn clean(s: &str) -> String {
+    s.replace("\\relax", "")
+        .replace(['{', '}'], "")
+        .replace('~', " ")
+        .trim()
+        .to_string()
+}
+
+#[test]
+fn test_from_aux() {
+    let aux = r"\relax
+\newlabel{sec:intro}{{1}{1}{Introduction}{section.1}{}}
+\newlabel{foo}{{2.3}{5}{}{thm.2.3}{}}
+\newlabel{foo@cref}{{[lemma][3][2]2.3}{[1][5][]5}}
+\newlabel{eq:a}{{4}{2}}
+\bibcite{knuth84}{{1}{1984}{{Knuth}}{{Knuth}}}
+\bibcite{lamport}{2}";
+    let resolver = Resolver::from_aux(aux);
+    let target = resolver.reference("foo").unwrap();
+    assert_eq!((&*target.kind, &*target.number), ("lemma", "2.3"));
+    assert_eq!(resolver.reference("sec:intro").unwrap().kind, "section");
+    assert_eq!(resolver.reference("eq:a").unwrap().number, "4");
+    let citation = resolver.citation("knuth84").unwrap();
+    assert_eq!(citation.author.as_deref(), Some("Knuth"));
+    assert_eq!(resolver.citation("lamport").unwrap().label, "2");
+}
@@ -0,0 +1,351 @@
+use std::fmt;
+use std::ops::Range;
+
+use super::command::Command;
+use super::{write_words, Ast, Position, Text, Word};
+use crate::token::NON_FINAL;
+
+/// A sentence of a paragraph: a range of its words.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Sentence<'a> {
+    pub words: &'a [Word],
+    /// The indices of the words in the paragraph.
+    pub range: Range<usize>,
+    /// Where the first text of the sentence starts in the source.
+    pub start: Option<Position>,
+    /// Where the last text of the sentence starts in the source.
+    pub end: Option<Position>,
+}
+
+impl fmt::Display for Sentence<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_words(f, self.words)
+    }
+}
+
+/// Abbreviations whose period does not end a sentence even before a capital letter, in lowercase.
+const ABBREVIATIONS: &[&str] = &[
+    "e.g", "i.e", "cf", "vs", "viz", "al", "ca", "approx", "resp", "fig", "figs", "eq", "eqs",
+    "sec", "secs", "ch", "chap", "app", "thm", "lem", "def", "prop", "cor", "alg", "tab", "ref",
+    "refs", "no", "nos", "vol", "pp", "p", "dr", "mr", "mrs", "ms", "prof", "st", "jr", "sr",
+    "n.d",
+];
+
+/// Splits the words of a paragraph into sentences.
+/// Headings and list items start new sentences.
+pub fn split(words: &[Word]) -> Vec<Sentence<'_>> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut ends = false;
+    for i in 0..words.len() {
+        let next = words.get(i + 1);
+        // a word glued to the end of a sentence, such as a footnote mark, ends it instead
+        ends = ends_sentence(&words[i]) || ends && is_glued(&words[i]);
+        let starts = match next {
+            Some(next) => starts_sentence(next),
+            None => true,
+        };
+        let breaks = is_break(&words[i]) || next.is_some_and(is_break) || ends && starts;
+        if breaks || next.is_none() {
+            sentences.push(sentence(words, start..i + 1));
+            start = i + 1;
+        }
+    }
+    sentences
+}
+
+fn sentence(words: &[Word], range: Range<usize>) -> Sentence<'_> {
+    let words = &words[range.clone()];
+    let mut positions = words.iter().filter_map(|word| first_text(word)?.position);
+    let start = positions.next();
+    let end = positions.next_back().or(start);
+    Sentence {
+        words,
+        range,
+        start,
+        end,
+    }
+}
+
+/// Whether a word stands apart from the sentences around it.
+fn is_break(word: &Word) -> bool {
+    matches!(
+        word,
+        Word::Command(Command::Section(..) | Command::Item)
+            | Word::Env(..)
+            | Word::Theorem(_)
+            | Word::List(_)
+            | Word::Float(_)
+            | Word::Table(_)
+    ) && !word.is_math()
+}
+
+/// Whether a word ends with a period, `?` or `!` which ends a sentence.
+fn ends_sentence(word: &Word) -> bool {
+    let Some(text) = last_text(word) else {
+        return false;
+    };
+    let s = text.s.trim_end_matches(['"', '\'', ')', ']', '’', '”']);
+    if s.ends_with(['?', '!']) {
+        return true;
+    }
+    let Some(stem) = s.strip_suffix('.') else {
+        return false;
+    };
+    // `\@.` marks a period which does not end a sentence; that of `.\ ` is not the last
+    if stem.ends_with(NON_FINAL) {
+        return false;
+    }
+    let stem = stem.rsplit([' ', '(', '[']).next().unwrap_or(stem);
+    let initial = stem.chars().count() == 1 && stem.starts_with(char::is_uppercase);
+    !initial && !ABBREVIATIONS.contains(&&*stem.to_lowercase())
+}
+
+/// Whether a word can start a sentence: not a word in lowercase, which continues one,
+/// nor a word glued to the one before such as a footnote mark.
+fn starts_sentence(word: &Word) -> bool {
+    match first_text(word) {
+        Some(text) => !text.glued && !text.s.starts_with(char::is_lowercase),
+        None => true,
+    }
+}
+
+fn is_glued(word: &Word) -> bool {
+    first_text(word).is_some_and(|text| text.glued)
+}
+
+fn first_text(word: &Word) -> Option<&Text> {
+    match word {
+        Word::Text(text) => Some(text),
+        Word::Lines(ast) => first_text(ast.paragraphs().first()?.words().first()?),
+        _ => None,
+    }
+}
+
+fn last_text(word: &Word) -> Option<&Text> {
+    match word {
+        Word::Text(text) => Some(text),
+        Word::Lines(ast) => last_text(ast.paragraphs().last()?.words().last()?),
+        _ => None,
+    }
+}
+
+/// The paragraphs of `ast` with a sentence on each line. The paragraphs of environments such as
+/// lists and theorems are written separately.
+pub(super) fn lines(ast: &Ast, prefix: Option<Option<&str>>) -> Vec<String> {
+    let mut writer = LineWriter {
+        prefix,
+        marker: None,
+        lines: Vec::new(),
+        paragraphs: Vec::new(),
+    };
+    writer.ast(ast);
+    writer.paragraphs
+}
+
+/// Writes sentences on their own lines, paragraph by paragraph.
+struct LineWriter<'a> {
+    prefix: Option<Option<&'a str>>,
+    /// A list marker or the name of a caption, which goes on the line of the next sentence.
+    marker: Option<String>,
+    /// The lines of the current paragraph.
+    lines: Vec<String>,
+    paragraphs: Vec<String>,
+}
+
+impl LineWriter<'_> {
+    fn ast(&mut self, ast: &Ast) {
+        for paragraph in ast.paragraphs() {
+            for sentence in paragraph.sentences() {
+                match sentence.words {
+                    [word] if self.block(word) => {}
+                    _ => self.sentence(&sentence),
+                }
+            }
+            self.flush();
+        }
+    }
+
+    /// Ends the current paragraph.
+    fn flush(&mut self) {
+        if !self.lines.is_empty() {
+            let lines = std::mem::take(&mut self.lines);
+            self.paragraphs.push(lines.join("\n"));
+        }
+    }
+
+    /// Writes the paragraphs written by `write` as one paragraph, as the items of a list are.
+    fn together(&mut self, write: impl FnOnce(&mut Self)) {
+        self.flush();
+        let outer = std::mem::take(&mut self.paragraphs);
+        write(self);
+        self.flush();
+        let inner = std::mem::replace(&mut self.paragraphs, outer);
+        if !inner.is_empty() {
+            self.paragraphs.push(inner.join("\n"));
+        }
+    }
+
+    /// Writes a word standing apart from the sentences, such as a list.
+    /// Returns whether the word is such a block.
+    fn block(&mut self, word: &Word) -> bool {
+        match word {
+            Word::Env(_, body) if !word.is_math() => {
+                self.flush();
+                self.ast(body);
+            }
+            Word::Theorem(theorem) => {
+                self.flush();
+                // the heading goes on a line of its own above the body
+                let start = self.paragraphs.len();
+                self.ast(&theorem.body);
+                match self.paragraphs.get_mut(start) {
+                    Some(first) => first.insert_str(0, &format!("{}\n", theorem.heading())),
+                    None => self.paragraphs.push(theorem.heading()),
+                }
+                if theorem.qed {
+                    self.paragraphs.last_mut().unwrap().push_str(" ∎");
+                }
+            }
+            // the items of a list and of the lists in it go together
+            Word::List(list) => self.together(|writer| {
+                for item in &list.items {
+                    writer.marker = Some(format!("{}{}", list.indent(), list.marker(item)));
+                    writer.ast(&item.body);
+                }
+            }),
+            Word::Table(table) => {
+                self.together(|writer| table.cells().for_each(|cell| writer.ast(cell)))
+            }
+            Word::Float(float) => {
+                self.flush();
+                for caption in &float.captions {
+                    self.marker = caption.name.as_ref().map(|name| format!("{name}:"));
+                    self.ast(&caption.text);
+                }
+                for table in &float.tables {
+                    self.together(|writer| table.cells().for_each(|cell| writer.ast(cell)));
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn sentence(&mut self, sentence: &Sentence) {
+        let text = sentence.to_string();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return;
+        }
+        // a list marker goes on the line of the first sentence of its item
+        if let [Word::Command(Command::Item)] = sentence.words {
+            self.marker = Some(text);
+            return;
+        }
+        let text = match self.marker.take() {
+            Some(marker) => format!("{marker} {text}"),
+            None => text,
+        };
+        // text made up by the conversion, such as a heading, has no position
+        let line = match (self.prefix, sentence.start) {
+            (Some(Some(file)), Some(start)) => format!("{file}:{}: {text}", start.line),
+            (Some(None), Some(start)) => format!("{}: {text}", start.line),
+            _ => text,
+        };
+        self.lines.push(line);
+    }
+}
+
+#[test]
+fn test_split() {
+    use crate::ast;
+    use std::str::FromStr;
+    let s = r"We use Fig.~3, e.g. this one. Then Dr. Smith and D. E. Knuth said $x$.
+NASA\@. Is here! Next \cite{a}. \emph{Really?} Yes, e.g.\ here. Next sentence starts.
+\section{End} Last";
+    let doc = crate::token::Document::from_str(s).unwrap();
+    let mut ast = ast::convert(None, doc, ast::Resolver::default());
+    crate::arrange::normalize(&mut ast, &Default::default());
+    let paragraph = &ast.paragraphs()[0];
+    let sentences = paragraph.sentences();
+    let sentences = sentences.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        sentences,
+        [
+            "We use Fig. 3, e.g. this one.",
+            "Then Dr. Smith and D. E. Knuth said XX.",
+            "NASA. Is here!",
+            "Next [AX].",
+            "Really?",
+            "Yes, e.g. here.",
+            "Next sentence starts."
+        ]
+    );
+    // a heading ends the paragraph
+    assert_eq!(ast.paragraphs()[1].to_string(), "# End");
+    assert_eq!(ast.paragraphs()[2].to_string(), "Last");
+    let second = &paragraph.sentences()[1];
+    assert_eq!(
+        second.start,
+        Some(Position {
+            line: 1,
+            column: 31
+        })
+    );
+    assert_eq!(
+        second.end,
+        Some(Position {
+            line: 1,
+            column: 70
+        })
+    );
+}
+
+#[test]
+fn test_lines() {
+    use std::str::FromStr;
+    let s = r"First one. Second one.
+\begin{itemize}
+\item An item. More of it.
+\item Another.
+\end{itemize}
+\begin{theorem}
+A claim. It holds.
+\end{theorem}
+\begin{figure}
+\caption{A figure. With notes.}
+\begin{tabular}{ll} a & b \end{tabular}
+\end{figure}
+Last.";
+    let doc = crate::token::Document::from_str(s).unwrap();
+    let mut ast = super::convert(None, doc, super::Resolver::default());
+    crate::arrange::normalize(&mut ast, &Default::default());
+    assert_eq!(
+        ast.sentence_lines(None),
+        [
+            "First one.\nSecond one.",
+            "- An item.\nMore of it.\n- Another.",
+            "Theorem.\nA claim.\nIt holds.",
+            "Figure 1: A figure.\nWith notes.",
+            "a\nb",
+            "Last."
+        ]
+    );
+    // a heading made up by the conversion has no position
+    assert_eq!(
+        ast.sentence_lines(Some(Some("a.tex"))),
+        [
+            "a.tex:1: First one.\na.tex:1: Second one.",
+            "a.tex:3: - An item.\na.tex:3: More of it.\na.tex:4: - Another.",
+            "Theorem.\na.tex:7: A claim.\na.tex:7: It holds.",
+            "a.tex:10: Figure 1: A figure.\na.tex:10: With notes.",
+            "a.tex:11: a\na.tex:11: b",
+            "a.tex:13: Last."
+        ]
+    );
+    assert_eq!(
+        ast.sentence_lines(Some(None))[0],
+        "1: First one.\n1: Second one."
+    );
+}
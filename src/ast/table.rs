@@ -0,0 +1,143 @@
+use std::fmt;
+
+use super::token_to_ast::{take_group, take_optional, token_to_ast};
+use super::{Ast, Context, Word};
+use crate::token;
+
+/// The cells of a `tabular`, row by row. Rules such as `\hline` and the column specification
+/// are dropped.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Table {
+    pub rows: Vec<Vec<Ast>>,
+}
+
+/// Table environments and the arguments before their bodies.
+const TABLES: [(&str, &str); 6] = [
+    ("tabular", "om"),
+    ("tabular*", "mom"),
+    ("tabularx", "mm"),
+    ("tabulary", "mm"),
+    ("longtable", "om"),
+    ("supertabular", "om"),
+];
+
+/// Converts the body of a table environment.
+/// Gives the body back for other environments.
+pub(super) fn token_to_table(
+    env: &str,
+    mut doc: token::Document,
+    ctx: &mut Context,
+) -> Result<Table, token::Document> {
+    let Some((_, spec)) = TABLES.iter().find(|(table, _)| *table == env) else {
+        return Err(doc);
+    };
+    // the column specification and the like
+    for kind in spec.chars() {
+        let _ = match kind {
+            'o' => take_optional(&mut doc),
+            _ => take_group(&mut doc),
+        };
+    }
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = token::Document::default();
+    let mut words = doc.words.into_iter().peekable();
+    while let Some(word) = words.next() {
+        match word {
+            token::Word::Alignment => row.push(std::mem::take(&mut cell)),
+            token::Word::Command(token::Command::Symbol('\\') | token::Command::LineBreak) => {
+                // `\\*[1ex]`
+                words.next_if(|w| matches!(w, token::Word::Text(s, _) if s == "*"));
+                words.next_if(|w| matches!(w, token::Word::Optional(_)));
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            word => cell.words.push(word),
+        }
+    }
+    row.push(cell);
+    rows.push(row);
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|cell| token_to_ast(cell, ctx))
+                .collect()
+        })
+        .collect();
+    Ok(Table { rows })
+}
+
+impl Table {
+    /// The cells with some text, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = &Ast> {
+        self.rows
+            .iter()
+            .flatten()
+            .filter(|cell| !cell.paragraphs().is_empty())
+    }
+}
+
+/// Drops the tables from `ast`, keeping the text around them.
+pub fn drop_tables(ast: &mut Ast) {
+    for paragraph in ast.paragraphs_mut() {
+        paragraph
+            .words_mut()
+            .retain(|word| !matches!(word, Word::Table(_)));
+        for word in paragraph.words_mut() {
+            match word {
+                Word::Lines(ast) | Word::Env(_, ast) => drop_tables(ast),
+                Word::Theorem(theorem) => drop_tables(&mut theorem.body),
+                Word::Float(float) => float.tables.clear(),
+                Word::List(list) => {
+                    for item in &mut list.items {
+                        drop_tables(&mut item.body);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl fmt::Display for Table {
+    /// Writes each cell on its own line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut iter = self.cells().peekable();
+        while let Some(cell) = iter.next() {
+            write!(f, "{cell}")?;
+            if iter.peek().is_some() {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_tables() {
+    use std::str::FromStr;
+    let s = r"Before \begin{tabular}[t]{l|cc} \toprule
+Name & \multicolumn{2}{c}{Scores}\\ \hline
+Alice&1 & 2 \\[2pt] \cline{2-3}
+\textbf{Bob} & & 3 \\ \bottomrule
+\end{tabular} after.";
+    let doc = token::Document::from_str(s).unwrap();
+    let mut ast = super::convert(None, doc, super::Resolver::default());
+    let lines = format!("{ast}")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        ["Before", "Name", "Scores", "Alice", "1", "2", "Bob", "3", "after."]
+    );
+    drop_tables(&mut ast);
+    let s = format!("{ast}")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    assert_eq!(s, "Before after.");
+}
@@ -0,0 +1,94 @@
+use std::fmt;
+
+use super::token_to_ast::take_optional;
+use super::{token_to_ast, Ast, Context};
+use crate::token;
+
+/// A theorem-like environment or a proof, e.g. `Theorem 2.1 (Title). Body`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Theorem {
+    /// The name and the number, e.g. `Theorem 2.1`.
+    pub name: String,
+    /// The optional argument, e.g. `\begin{theorem}[Title]`.
+    pub title: Option<Ast>,
+    pub body: Ast,
+    /// Whether the body ends with a QED mark, as a proof does.
+    pub qed: bool,
+}
+
+impl Theorem {
+    /// The heading before the body, e.g. `Theorem 2.1 (Title).`
+    pub fn heading(&self) -> String {
+        match &self.title {
+            Some(title) => format!("{} ({}).", self.name, format!("{title}").trim()),
+            None => format!("{}.", self.name),
+        }
+    }
+}
+
+/// Converts the body of a theorem-like environment or a proof, the environment being entered.
+/// Gives the body back for other environments.
+pub(super) fn token_to_theorem(
+    env: &str,
+    mut doc: token::Document,
+    ctx: &mut Context,
+) -> Result<Theorem, token::Document> {
+    let name = match (env, ctx.theorem_name(env)) {
+        (_, Some(name)) => name,
+        ("proof", None) => "Proof".to_string(),
+        _ => return Err(doc),
+    };
+    // an empty title as in `\begin{proof}[]` is no title
+    let title = take_optional(&mut doc)
+        .map(|title| token_to_ast(title, ctx))
+        .filter(|title| !format!("{title}").trim().is_empty());
+    let body = token_to_ast(doc, ctx);
+    let theorem = match (env, title) {
+        // `\begin{proof}[Proof of the lemma]` replaces the name
+        ("proof", Some(title)) => Theorem {
+            name: format!("{title}").trim().to_string(),
+            title: None,
+            body,
+            qed: true,
+        },
+        (_, title) => Theorem {
+            name,
+            title,
+            body,
+            qed: env == "proof",
+        },
+    };
+    Ok(theorem)
+}
+
+impl fmt::Display for Theorem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.heading(), self.body)?;
+        if self.qed {
+            write!(f, " ∎")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_theorems() {
+    use std::str::FromStr;
+    let preamble = r"\newtheorem{thm}{Theorem}[section] \newtheorem*{claim}{Claim}";
+    let s = r"\section{A} \begin{thm}[Fermat]\label{f} No solutions.\end{thm}
+\begin{proof} Too long. \end{proof} \begin{claim} True. \end{claim}
+\begin{lemma} Known. \end{lemma} \begin{proof}[Proof of \cref{f}] Easy. \end{proof}
+\begin{proof}[]Clear. \end{proof}";
+    let preamble = token::Document::from_str(preamble).unwrap();
+    let doc = token::Document::from_str(s).unwrap();
+    let ast = super::convert(Some(preamble), doc, super::Resolver::default());
+    let s = format!("{ast}")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    assert_eq!(
+        s,
+        "# A Theorem 1.1 (Fermat). No solutions. Proof. Too long. ∎ Claim. True. \
+        Lemma. Known. Proof of theorem 1.1. Easy. ∎ Proof. Clear. ∎"
+    );
+}
@@ -3,12 +3,18 @@ use std::iter::Peekable;
 use crate::ast;
 use crate::token;
 
+use super::beamer::{is_frame, token_to_block, token_to_frame};
 use super::command::token_to_ast_command;
-use super::Ast;
+use super::context::float_counter;
+use super::front::{front_section, is_title_page, make_title, section_heading};
+use super::list::token_to_list;
+use super::table::token_to_table;
+use super::theorem::token_to_theorem;
+use super::{Ast, Context};
 
 type PeekableWords = Peekable<Box<dyn Iterator<Item = token::Word>>>;
 
-pub fn token_to_ast(doc: token::Document) -> ast::Ast {
+pub fn token_to_ast(doc: token::Document, ctx: &mut Context) -> ast::Ast {
     let words_iter: Box<dyn Iterator<Item = token::Word>> = Box::new(doc.words.into_iter());
     let mut words: PeekableWords = words_iter.peekable();
     let mut ast = ast::Ast::new();
@@ -19,61 +25,47 @@ pub fn token_to_ast(doc: token::Document) -> ast::Ast {
 
     while let Some(word) = words.next() {
         match word {
-            token::Word::Text(s) => {
-                paragraph.push(ast::Word::Text(s));
+            token::Word::Text(s, position) => {
+                let text = ast::Text::at(token::ligatures(&s), position);
+                paragraph.push(ast::Word::Text(text));
+            }
+            token::Word::Command(token::Command::MakeTitle) => {
+                push_block(&mut ast, &mut paragraph, make_title(ctx));
+            }
+            token::Word::Command(token::Command::TitlePage) if ctx.is_beamer() => {
+                push_block(&mut ast, &mut paragraph, make_title(ctx));
+            }
+            token::Word::Command(c @ token::Command::Frame) if ctx.is_beamer() => {
+                let body = take_args(&mut words, &c).pop().flatten();
+                ctx.enter_env("frame");
+                let frame = env_to_ast("frame".to_string(), body.unwrap_or_default(), ctx);
+                ctx.leave_env();
+                push_word(&mut ast, &mut paragraph, frame, ctx);
             }
             token::Word::Command(c) => {
-                let mut args = Vec::new();
-                match c.arg_num() {
-                    Some(n) => {
-                        let mut iter: Box<dyn Iterator<Item = token::Word>> = Box::new(words);
-                        for _ in 0..n {
-                            iter =
-                                Box::new(iter.skip_while(|w| !matches!(w, token::Word::Lines(_))));
-                            if let Some(token::Word::Lines(doc)) = iter.next() {
-                                args.push(doc);
-                            } else {
-                                eprintln!("Error: Command {:?} needs {} arguments", c, n);
-                            }
-                        }
-                        words = iter.peekable();
-                    }
-                    None => {
-                        while let Some(token::Word::Lines(doc)) =
-                            words.next_if(|w| matches!(w, token::Word::Lines(_)))
-                        {
-                            args.push(doc);
-                        }
-                    }
+                let args = take_args(&mut words, &c);
+                if let Some(w) = token_to_ast_command(c, args, ctx) {
+                    push_word(&mut ast, &mut paragraph, w, ctx);
                 }
-                if let Some(w) = token_to_ast_command(c, args) {
-                    paragraph.push(w);
-                }
-
-                // while let Some(token::Word::Text(s)) = words.peek() {
-                //     args.push(words.next().unwrap());
-                // }
-                // paragraph.push(doc_to_ast_command(c, args));
             }
             token::Word::Lines(doc) => {
-                let Ast(mut ps) = token_to_ast(doc);
-                if ps.len() > 1 {
-                    let first_p = ps.remove(0);
-                    paragraph.0.extend(first_p.0);
-                    ast.0.push(paragraph);
-                    paragraph = match ps.pop() {
-                        Some(p) => p,
-                        None => ast::Paragraph::new(),
-                    };
-                    ast.0.extend(ps);
-                } else if ps.len() == 1 {
-                    let new_p = ps.remove(0);
-                    paragraph.0.extend(new_p.0);
-                }
+                extend_lines(&mut ast, &mut paragraph, doc, ctx);
             }
-            token::Word::Comment(_) => continue,
-            token::Word::Env(s, d) => {
-                paragraph.0.push(ast::Word::Env(s, token_to_ast(d)));
+            token::Word::Optional(doc) => {
+                paragraph.push(ast::Word::Text(ast::Text::new("[")));
+                extend_lines(&mut ast, &mut paragraph, doc, ctx);
+                paragraph.push(ast::Word::Text(ast::Text::new("]")));
+            }
+            // the columns of display math are not rendered
+            token::Word::Comment(_) | token::Word::Alignment => continue,
+            token::Word::Env(s, mut d) => {
+                ctx.enter_env(&s);
+                if ctx.in_rows() {
+                    strip_last_row_end(&mut d);
+                }
+                let env = env_to_ast(s, d, ctx);
+                ctx.leave_env();
+                push_word(&mut ast, &mut paragraph, env, ctx);
             }
             token::Word::Dollar => {
                 let mut s = String::new();
@@ -96,12 +88,178 @@ pub fn token_to_ast(doc: token::Document) -> ast::Ast {
             }
         }
     }
-    if paragraph.0.len() > 0 {
+    if !paragraph.0.is_empty() {
         ast.push(paragraph);
     }
     ast
 }
 
+/// Adds a converted word to the current paragraph. Headings are paragraphs of their own,
+/// and so are the sections of the front matter such as the abstract, title pages and beamer frames.
+fn push_word(ast: &mut Ast, paragraph: &mut ast::Paragraph, word: ast::Word, ctx: &Context) {
+    let block = match word {
+        w @ ast::Word::Command(ast::Command::Section(..)) => vec![ast::Paragraph(vec![w])],
+        // the heading of a frame starts its body
+        ast::Word::Env(env, body) if is_frame(&env) => body.0,
+        ast::Word::Env(env, body) if is_title_page(&env) => body.0,
+        ast::Word::Env(env, body) => match section_heading(&env) {
+            Some(heading) => front_section(heading, body, ctx),
+            None => return paragraph.push(ast::Word::Env(env, body)),
+        },
+        w => return paragraph.push(w),
+    };
+    push_block(ast, paragraph, block);
+}
+
+/// Ends the current paragraph and adds paragraphs after it.
+fn push_block(ast: &mut Ast, paragraph: &mut ast::Paragraph, block: Vec<ast::Paragraph>) {
+    if !paragraph.0.is_empty() {
+        ast.push(std::mem::take(paragraph));
+    }
+    ast.0.extend(block);
+}
+
+/// Converts an environment which `ctx` has entered.
+fn env_to_ast(env: String, doc: token::Document, ctx: &mut Context) -> ast::Word {
+    let doc = match token_to_list(&env, doc, ctx) {
+        Ok(list) => return ast::Word::List(list),
+        Err(doc) => doc,
+    };
+    let doc = match token_to_theorem(&env, doc, ctx) {
+        Ok(theorem) => return ast::Word::Theorem(theorem),
+        Err(doc) => doc,
+    };
+    let doc = match token_to_block(&env, doc, ctx) {
+        Ok(block) => return ast::Word::Theorem(block),
+        Err(doc) => doc,
+    };
+    let doc = match token_to_table(&env, doc, ctx) {
+        Ok(table) => return ast::Word::Table(table),
+        Err(doc) => doc,
+    };
+    let doc = match token_to_frame(&env, doc, ctx) {
+        Ok(frame) => return ast::Word::Env(env, frame),
+        Err(doc) => doc,
+    };
+    let body = token_to_ast(doc, ctx);
+    match float_counter(&env) {
+        Some(_) => ast::Word::Float(ast::Float::new(env, body)),
+        None => ast::Word::Env(env, body),
+    }
+}
+
+/// Drops a `\\` ending the last row of display math, which does not start a new row.
+fn strip_last_row_end(doc: &mut token::Document) {
+    while matches!(
+        doc.words.last(),
+        Some(token::Word::EndLine | token::Word::Comment(_))
+    ) {
+        doc.words.pop();
+    }
+    if doc.words.last() == Some(&token::Word::Command(token::Command::Symbol('\\'))) {
+        doc.words.pop();
+    }
+}
+
+/// Takes the optional argument at the start of an environment.
+pub(super) fn take_optional(doc: &mut token::Document) -> Option<token::Document> {
+    let i = doc
+        .words
+        .iter()
+        .position(|w| !matches!(w, token::Word::EndLine | token::Word::Comment(_)))?;
+    match doc.words[i] {
+        token::Word::Optional(_) => match doc.words.remove(i) {
+            token::Word::Optional(title) => Some(title),
+            _ => unreachable!(),
+        },
+        _ => None,
+    }
+}
+
+/// Takes the mandatory argument at the start of an environment.
+pub(super) fn take_group(doc: &mut token::Document) -> Option<token::Document> {
+    let i = doc
+        .words
+        .iter()
+        .position(|w| !matches!(w, token::Word::EndLine | token::Word::Comment(_)))?;
+    match doc.words[i] {
+        token::Word::Lines(_) => match doc.words.remove(i) {
+            token::Word::Lines(group) => Some(group),
+            _ => unreachable!(),
+        },
+        _ => None,
+    }
+}
+
+/// Appends the paragraphs of a group to the current paragraph:
+/// the first one continues it and the last one becomes the new current paragraph.
+fn extend_lines(
+    ast: &mut Ast,
+    paragraph: &mut ast::Paragraph,
+    doc: token::Document,
+    ctx: &mut Context,
+) {
+    let Ast(mut ps) = token_to_ast(doc, ctx);
+    if ps.len() > 1 {
+        let first_p = ps.remove(0);
+        paragraph.0.extend(first_p.0);
+        let last_p = ps.pop().unwrap_or_default();
+        ast.0.push(std::mem::replace(paragraph, last_p));
+        ast.0.extend(ps);
+    } else if ps.len() == 1 {
+        let new_p = ps.remove(0);
+        paragraph.0.extend(new_p.0);
+    }
+}
+
+/// Takes the arguments of `c` following its argument specification.
+/// A star or an optional argument is `None` when absent;
+/// a missing mandatory argument is reported and replaced by an empty one.
+fn take_args(words: &mut PeekableWords, c: &token::Command) -> Vec<Option<token::Document>> {
+    let mut args = Vec::new();
+    let Some(spec) = c.arg_spec() else {
+        while let Some(token::Word::Lines(doc)) =
+            words.next_if(|w| matches!(w, token::Word::Lines(_)))
+        {
+            args.push(Some(doc));
+        }
+        return args;
+    };
+    for kind in spec.chars() {
+        let arg = match kind {
+            's' => match words.peek_mut() {
+                Some(token::Word::Text(s, _)) if s.starts_with('*') => {
+                    s.remove(0);
+                    if s.is_empty() {
+                        words.next();
+                    }
+                    Some(token::Document::default())
+                }
+                _ => None,
+            },
+            'o' => match words.next_if(|w| matches!(w, token::Word::Optional(_))) {
+                Some(token::Word::Optional(doc)) => Some(doc),
+                _ => None,
+            },
+            _ => {
+                while words
+                    .next_if(|w| matches!(w, token::Word::EndLine | token::Word::Comment(_)))
+                    .is_some()
+                {}
+                match words.next_if(|w| matches!(w, token::Word::Lines(_))) {
+                    Some(token::Word::Lines(doc)) => Some(doc),
+                    _ => {
+                        eprintln!("Error: Command {:?} needs an argument {:?}", c, spec);
+                        Some(token::Document::default())
+                    }
+                }
+            }
+        };
+        args.push(arg);
+    }
+    args
+}
+
 #[test]
 fn test_doc_to_ast() {
     use std::str::FromStr;
@@ -110,7 +268,118 @@ fn test_doc_to_ast() {
     let s = "{} a";
     let doc = token::Document::from_str(s).unwrap();
     println!("{}", doc);
-    let ast = token_to_ast(doc);
+    let ast = token_to_ast(doc, &mut Context::default());
     println!("{:?}", ast);
     println!("{}", ast);
 }
+
+#[test]
+fn test_known_commands() {
+    use std::str::FromStr;
+    let s = r"a \vspace{1em} b \includegraphics[width=\textwidth]{fig.pdf} \textcolor{red}{c} \href{http://x}{d}";
+    let doc = token::Document::from_str(s).unwrap();
+    let ast = token_to_ast(doc, &mut Context::default());
+    assert_eq!(format!("{ast}"), "a b c d");
+}
+
+#[test]
+fn test_label_names() {
+    use std::str::FromStr;
+    let preamble = r"\newtheorem{thm}{Theorem} \newtheorem{lem}[thm]{Lemma}";
+    let s = r"By \Cref{foo}. \section{A}\label{bar} \begin{lem}\label{foo}\end{lem} \cref{bar}";
+    let preamble = token::Document::from_str(preamble).unwrap();
+    let doc = token::Document::from_str(s).unwrap();
+    let ast = ast::convert(Some(preamble), doc, ast::Resolver::default());
+    let s = format!("{ast}");
+    assert!(s.starts_with("By Lemma 1"), "{s}");
+    assert!(s.contains("section 1"), "{s}");
+    // a lemma of a class file, whose number is not known
+    let s = r"\section{A}\begin{lemma}\label{l}x\end{lemma} see \cref{l} and \ref{l}";
+    let doc = token::Document::from_str(s).unwrap();
+    let ast = ast::convert(None, doc, ast::Resolver::default());
+    assert!(format!("{ast}").ends_with("see lemma and ??"), "{ast}");
+}
+
+#[test]
+fn test_math_in_optional() {
+    use std::str::FromStr;
+    let convert = |s| {
+        let doc = token::Document::from_str(s).unwrap();
+        let mut ast = ast::convert(None, doc, ast::Resolver::default());
+        crate::arrange::normalize(&mut ast, &Default::default());
+        format!("{ast}").trim().to_string()
+    };
+    assert_eq!(
+        convert(r"\begin{description}\item[$f$-vector] a count.\end{description}"),
+        "FX -vector: a count."
+    );
+    assert_eq!(
+        convert(r"\begin{theorem}[Bound on $n$] x\end{theorem}"),
+        "Theorem (Bound on NX). x"
+    );
+}
+
+#[test]
+fn test_ref_styles() {
+    use std::str::FromStr;
+    let s = r"\section{A}\label{a} \begin{equation}\label{b}\end{equation}
+Section~\ref{a} \eqref{b} \Cref{b} \autoref{a}";
+    let doc = token::Document::from_str(s).unwrap();
+    let mut ast = ast::convert(None, doc, ast::Resolver::default());
+    crate::arrange::normalize(&mut ast, &Default::default());
+    let s = format!("{ast}");
+    assert!(s.ends_with("Section 1 (1) Equation (1) Section 1"), "{s}");
+    // no type is made up for an unknown target
+    let doc = token::Document::from_str(r"\cref{thm:nope} \autoref{nope}").unwrap();
+    let ast = ast::convert(None, doc, ast::Resolver::default());
+    assert_eq!(format!("{ast}"), "?? ??");
+}
+
+#[test]
+fn test_multi_refs() {
+    use std::str::FromStr;
+    let s = r"\section{A}\label{a} \section{B}\label{b} \section{C}\label{c}
+\begin{equation}\label{e}\end{equation}
+\Cref{b,e,a} \cref{a,b,c} \crefrange{a}{c}";
+    let doc = token::Document::from_str(s).unwrap();
+    let ast = ast::convert(None, doc, ast::Resolver::default());
+    let s = format!("{ast}");
+    assert!(
+        s.ends_with("Sections 1 and 2 and Equation (1) sections 1 to 3 sections 1 to 3"),
+        "{s}"
+    );
+}
+
+#[test]
+fn test_quotes() {
+    use std::str::FromStr;
+    let s = r"``Don't'' say `users' data' \enquote{a \emph{b}}";
+    let doc = token::Document::from_str(s).unwrap();
+    let mut ast = ast::convert(None, doc, ast::Resolver::default());
+    let mut rules = crate::arrange::Rules::default();
+    crate::arrange::normalize(&mut ast, &rules);
+    assert_eq!(format!("{ast}"), "\"Don't\" say 'users' data' \"a b\"");
+    let doc = token::Document::from_str(s).unwrap();
+    let mut ast = ast::convert(None, doc, ast::Resolver::default());
+    rules.enable(crate::arrange::Rule::SmartQuotes);
+    crate::arrange::normalize(&mut ast, &rules);
+    assert_eq!(format!("{ast}"), "“Don’t” say ‘users’ data’ “a b”");
+}
+
+#[test]
+fn test_sectioning() {
+    use std::str::FromStr;
+    let preamble = token::Document::from_str(r"\documentclass[a4paper]{book}").unwrap();
+    let s = r"\part{P} \chapter{C}\label{c} \section*{S} \section[Short]{Long}\label{l}
+\paragraph{Para} Text \cref{c} \cref{l}. \appendix \chapter{D}\label{d} \Cref{d}";
+    let doc = token::Document::from_str(s).unwrap();
+    let mut ast = ast::convert(Some(preamble), doc, ast::Resolver::default());
+    crate::arrange::normalize(&mut ast, &Default::default());
+    assert_eq!(
+        format!("{ast}"),
+        "# P\n\n## C\n\n### S\n\n### Long\n\n###### Para\n\nText chapter 1 section 1.1.\n\n## D\n\nAppendix A"
+    );
+    let doc = token::Document::from_str(r"\subsection{A} \section{B} \subsubsection{C}").unwrap();
+    let ast = ast::convert(None, doc, ast::Resolver::default());
+    assert_eq!(format!("{ast}"), "## A\n\n# B\n\n### C");
+}
@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+
+/// The entries of BibTeX or BibLaTeX files.
+#[derive(Debug, Default)]
+pub struct Database {
+    entries: Vec<Entry>,
+    index: HashMap<String, usize>,
+}
+
+/// An entry such as `@article{knuth84, ...}`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// The entry type in lowercase, e.g. `article`.
+    pub kind: String,
+    pub key: String,
+    pub fields: Vec<Field>,
+    /// The byte offset of the entry in the file.
+    pub offset: usize,
+}
+
+/// A field with its value after `@string` substitution and concatenation.
+/// The value keeps its TeX markup but not its outer braces or quotes.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Field {
+    /// The field name in lowercase, e.g. `title`.
+    pub name: String,
+    pub value: String,
+    /// The byte offset of the value in the file.
+    pub offset: usize,
+}
+
+impl Database {
+    /// Parses a `.bib` file, skipping the entries it cannot read.
+    pub fn parse(s: &str) -> Self {
+        let mut db = Database::default();
+        db.extend(Parser::new(s).entries());
+        db
+    }
+
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = Entry>) {
+        for entry in entries {
+            self.index.insert(entry.key.clone(), self.entries.len());
+            self.entries.push(entry);
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.index.get(key).map(|&i| &self.entries[i])
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn into_entries(self) -> Vec<Entry> {
+        self.entries
+    }
+}
+
+impl Entry {
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// Splits a name list such as `Knuth, Donald E. and {Barnes and Noble}` at its top-level `and`s,
+/// which may be surrounded by any whitespace such as a line break.
+pub fn split_names(s: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut start = 0;
+    for (i, end) in words(s) {
+        if &s[i..end] == "and" {
+            names.push(s[start..i].trim());
+            start = end;
+        }
+    }
+    names.push(s[start..].trim());
+    names.retain(|name| !name.is_empty());
+    names
+}
+
+/// The last name with its particles, of `First von Last` or `von Last, First`.
+pub fn last_name(name: &str) -> &str {
+    if let Some(comma) = top_level(name).find(|&(_, ch)| ch == ',') {
+        return name[..comma.0].trim();
+    }
+    let words = words(name);
+    let Some(&(last, end)) = words.last() else {
+        return name.trim();
+    };
+    // the particles start at the first word in lowercase, as in `Ludwig van Beethoven`
+    let start = words[..words.len() - 1]
+        .iter()
+        .find(|&&(i, _)| name[i..].starts_with(char::is_lowercase))
+        .map_or(last, |&(i, _)| i);
+    &name[start..end]
+}
+
+/// The characters of `s` which are not in braces, with their byte offsets.
+fn top_level(s: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut depth = 0;
+    s.char_indices().filter(move |&(_, ch)| {
+        match ch {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => return depth == 0,
+        }
+        false
+    })
+}
+
+/// The byte ranges of the words of `s` separated by whitespace out of braces.
+fn words(s: &str) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut depth = 0;
+    for (i, ch) in s.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ if depth == 0 && ch.is_whitespace() => {
+                if let Some(start) = start.take() {
+                    words.push((start, i));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(start) = start {
+        words.push((start, s.len()));
+    }
+    words
+}
+
+/// Removes the braces that protect letters from case changes, as in `{B}ayesian`,
+/// keeping those of command arguments such as `\emph{all}` and groups such as `{\TeX}`.
+/// Returns the byte offsets in `s` of the removed braces with the text.
+pub fn strip_case_braces(s: &str) -> (String, Vec<usize>) {
+    let mut out = String::new();
+    let mut removed = Vec::new();
+    // whether each open brace is kept
+    let mut kept = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                out.push(ch);
+                // a command name, or a single escaped character
+                match chars.next_if(|(_, ch)| ch.is_ascii_alphabetic()) {
+                    Some((_, first)) => {
+                        out.push(first);
+                        while let Some((_, ch)) = chars.next_if(|(_, ch)| ch.is_ascii_alphabetic())
+                        {
+                            out.push(ch);
+                        }
+                    }
+                    None => out.extend(chars.next().map(|(_, ch)| ch)),
+                }
+                continue;
+            }
+            '{' => {
+                let is_arg = is_command_end(&out) || out.ends_with('}');
+                let keep = is_arg || matches!(chars.peek(), Some((_, '\\')));
+                kept.push(keep);
+                if !keep {
+                    removed.push(i);
+                    continue;
+                }
+            }
+            '}' if !kept.pop().unwrap_or(true) => {
+                removed.push(i);
+                continue;
+            }
+            _ => {}
+        }
+        out.push(ch);
+    }
+    (out, removed)
+}
+
+/// Whether `s` ends with a command such as `\emph` or `\"`.
+fn is_command_end(s: &str) -> bool {
+    let name = s.trim_end_matches(|ch: char| ch.is_ascii_alphabetic());
+    match name.len() < s.len() {
+        true => name.ends_with('\\'),
+        false => s.chars().rev().nth(1) == Some('\\') && !s.ends_with('}'),
+    }
+}
+
+/// The 1-based line and column of a byte offset in `s`.
+pub fn line_column(s: &str, offset: usize) -> (usize, usize) {
+    let before = &s[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count() + 1;
+    (line, before[line_start..].chars().count() + 1)
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+    /// `@string` abbreviations, in lowercase.
+    strings: HashMap<String, String>,
+}
+
+const MONTHS: [(&str, &str); 12] = [
+    ("jan", "January"),
+    ("feb", "February"),
+    ("mar", "March"),
+    ("apr", "April"),
+    ("may", "May"),
+    ("jun", "June"),
+    ("jul", "July"),
+    ("aug", "August"),
+    ("sep", "September"),
+    ("oct", "October"),
+    ("nov", "November"),
+    ("dec", "December"),
+];
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Self {
+        let strings = MONTHS
+            .iter()
+            .map(|&(abbr, month)| (abbr.to_string(), month.to_string()))
+            .collect();
+        Parser { s, pos: 0, strings }
+    }
+
+    /// Reads the entries. Text outside `@...` commands is a comment.
+    fn entries(mut self) -> Vec<Entry> {
+        let mut entries = Vec::new();
+        while let Some(at) = self.s[self.pos..].find('@') {
+            let offset = self.pos + at;
+            self.pos = offset + 1;
+            let kind = self.ident().to_lowercase();
+            self.skip_spaces();
+            let close = match self.next() {
+                Some('{') => '}',
+                Some('(') => ')',
+                _ => continue,
+            };
+            match &*kind {
+                "comment" | "preamble" => {
+                    self.skip_group(close);
+                }
+                "string" => {
+                    if let Some(fields) = self.fields(close) {
+                        for field in fields {
+                            self.strings.insert(field.name, field.value);
+                        }
+                    }
+                }
+                _ => match self.entry(kind, close, offset) {
+                    Some(entry) => entries.push(entry),
+                    None => log::warn!("failed to read the bib entry at byte {offset}"),
+                },
+            }
+        }
+        entries
+    }
+
+    fn entry(&mut self, kind: String, close: char, offset: usize) -> Option<Entry> {
+        self.skip_spaces();
+        let start = self.pos;
+        while !matches!(self.peek(), Some(',') | None) && self.peek() != Some(close) {
+            self.next();
+        }
+        let key = self.s[start..self.pos].trim().to_string();
+        let fields = self.fields(close)?;
+        Some(Entry {
+            kind,
+            key,
+            fields,
+            offset,
+        })
+    }
+
+    /// Reads `name = value` pairs up to the closing delimiter.
+    fn fields(&mut self, close: char) -> Option<Vec<Field>> {
+        let mut fields = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.peek()? {
+                ',' => {
+                    self.next();
+                    continue;
+                }
+                ch if ch == close => {
+                    self.next();
+                    return Some(fields);
+                }
+                _ => {}
+            }
+            let name = self.ident().to_lowercase();
+            self.skip_spaces();
+            if name.is_empty() || self.next()? != '=' {
+                return None;
+            }
+            let (value, offset) = self.value()?;
+            fields.push(Field {
+                name,
+                value,
+                offset,
+            });
+        }
+    }
+
+    /// Reads a value: braced or quoted strings, numbers and abbreviations concatenated by `#`.
+    fn value(&mut self) -> Option<(String, usize)> {
+        let mut value = String::new();
+        let mut offset = None;
+        loop {
+            self.skip_spaces();
+            let start = self.pos;
+            match self.peek()? {
+                '{' => {
+                    self.next();
+                    offset.get_or_insert(self.pos);
+                    value += self.skip_group('}')?;
+                }
+                '"' => {
+                    self.next();
+                    offset.get_or_insert(self.pos);
+                    value += self.quoted()?;
+                }
+                ch if ch.is_ascii_digit() => {
+                    offset.get_or_insert(start);
+                    while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+                        self.next();
+                    }
+                    value += &self.s[start..self.pos];
+                }
+                _ => {
+                    offset.get_or_insert(start);
+                    let name = self.ident().to_lowercase();
+                    if name.is_empty() {
+                        return None;
+                    }
+                    match self.strings.get(&name) {
+                        Some(s) => value += s,
+                        None => log::warn!("undefined bib string `{name}`"),
+                    }
+                }
+            }
+            self.skip_spaces();
+            if self.peek() != Some('#') {
+                return Some((value, offset.unwrap_or(self.pos)));
+            }
+            self.next();
+        }
+    }
+
+    /// Skips a group whose opening delimiter has been read, and returns its content.
+    fn skip_group(&mut self, close: char) -> Option<&'a str> {
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            let ch = self.next()?;
+            match ch {
+                '{' => depth += 1,
+                '}' if depth > 0 => depth -= 1,
+                _ if ch == close && depth == 0 => return Some(&self.s[start..self.pos - 1]),
+                _ => {}
+            }
+        }
+    }
+
+    /// Reads a `"..."` string whose opening quote has been read. Quotes in braces do not end it.
+    fn quoted(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                '"' if depth == 0 => return Some(&self.s[start..self.pos - 1]),
+                _ => {}
+            }
+        }
+    }
+
+    fn ident(&mut self) -> &'a str {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|ch| !ch.is_whitespace() && !"\"#%'(),={}".contains(ch))
+        {
+            self.next();
+        }
+        &self.s[start..self.pos]
+    }
+
+    /// Skips white spaces and `%` comments between fields.
+    fn skip_spaces(&mut self) {
+        loop {
+            match self.peek() {
+                Some(ch) if ch.is_whitespace() => {
+                    self.next();
+                }
+                Some('%') => while !matches!(self.next(), Some('\n') | None) {},
+                _ => return,
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+}
+
+#[test]
+fn test_parse() {
+    let s = r#"% a comment
+@string{ acm = "ACM" }
+@comment{ @article{not, title = {an entry}} }
+@book{knuth84,
+  author = {Knuth, Donald E.},
+  title  = "The {\TeX}book",
+  publisher = acm # { Press},
+  year = 1984, month = sep,
+}
+@article(lamport, author = {Leslie Lamport and {Barnes and Noble}}, title = {{B}ayesian}
+)
+"#;
+    let db = Database::parse(s);
+    assert_eq!(db.entries().len(), 2);
+    let knuth = db.get("knuth84").unwrap();
+    assert_eq!(knuth.kind, "book");
+    assert_eq!(knuth.field("title").unwrap().value, r"The {\TeX}book");
+    assert_eq!(knuth.field("publisher").unwrap().value, "ACM Press");
+    assert_eq!(knuth.field("year").unwrap().value, "1984");
+    assert_eq!(knuth.field("month").unwrap().value, "September");
+    let title = knuth.field("title").unwrap();
+    assert!(s[title.offset..].starts_with("The"));
+    let lamport = db.get("lamport").unwrap();
+    let authors = split_names(&lamport.field("author").unwrap().value);
+    assert_eq!(authors, ["Leslie Lamport", "{Barnes and Noble}"]);
+    assert_eq!(last_name(authors[0]), "Lamport");
+    assert_eq!(last_name("Knuth, Donald E."), "Knuth");
+    assert_eq!(line_column(s, title.offset), (6, 13));
+    let (stripped, removed) = strip_case_braces(r"{{B}ayesian} \emph{all} {\TeX}");
+    assert_eq!(stripped, r"Bayesian \emph{all} {\TeX}");
+    assert_eq!(removed, [0, 1, 3, 11]);
+    assert_eq!(
+        strip_case_braces(r#"G\"{o}del \frac{a}{b}"#).0,
+        r#"G\"{o}del \frac{a}{b}"#
+    );
+}
+
+#[test]
+fn test_names() {
+    let db = Database::parse(
+        "@article{three, author = {Leslie Lamport and\n    John Smith and\n\tJones, Ann}}",
+    );
+    let authors = split_names(&db.get("three").unwrap().field("author").unwrap().value);
+    assert_eq!(authors, ["Leslie Lamport", "John Smith", "Jones, Ann"]);
+    assert_eq!(last_name("Ludwig van Beethoven"), "van Beethoven");
+    assert_eq!(last_name("Jean de la Fontaine"), "de la Fontaine");
+    assert_eq!(last_name("de la Fontaine, Jean"), "de la Fontaine");
+    assert_eq!(last_name("Brinch Hansen, Per"), "Brinch Hansen");
+    assert_eq!(last_name("{Barnes, Inc.}"), "{Barnes, Inc.}");
+    assert_eq!(last_name(r"{\'E}mile Zola"), "Zola");
+}
@@ -1,5 +1,6 @@
 use crate::arrange;
-use crate::preprocess::preprocess;
+use crate::bib;
+use crate::preprocess::{body_start, preprocess};
 
 use super::super::ast;
 
@@ -8,7 +9,7 @@ use anyhow::Result;
 use arboard::Clipboard;
 use std::fs::read_to_string;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use structopt::StructOpt;
 
@@ -20,6 +21,105 @@ pub struct Opts {
     /// Output file
     #[structopt(short)]
     output: Option<PathBuf>,
+
+    /// Normalization rules to enable: ascii-dashes to write en and em dashes as `-` and `--`,
+    /// smart-quotes to write typographic quotes such as “ and ’
+    #[structopt(long)]
+    enable: Vec<arrange::Rule>,
+
+    /// Normalization rules to disable: whitespace, punctuation, brackets
+    #[structopt(long)]
+    disable: Vec<arrange::Rule>,
+
+    /// Output format: text, or sentences for one sentence per line
+    #[structopt(long, default_value = "text")]
+    format: Format,
+
+    /// Prefix each sentence with its source `file:line`
+    #[structopt(long)]
+    positions: bool,
+
+    /// What to do with tables: drop them, or write each cell on its own line
+    #[structopt(long, default_value = "drop")]
+    tables: Tables,
+
+    /// Where to put footnotes: after their paragraph, at the end, or drop them
+    #[structopt(long, default_value = "after")]
+    footnotes: ast::Placement,
+
+    /// Keep the author block under the title of `\maketitle`
+    #[structopt(long)]
+    authors: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    Text,
+    /// One sentence per line, with a blank line between paragraphs.
+    Sentences,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "sentences" => Ok(Format::Sentences),
+            _ => Err(format!("unknown format: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Tables {
+    Drop,
+    /// One cell per line.
+    Cells,
+}
+
+impl FromStr for Tables {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop" => Ok(Tables::Drop),
+            "cells" => Ok(Tables::Cells),
+            _ => Err(format!("unknown table mode: {s}")),
+        }
+    }
+}
+
+/// How to write the converted text.
+struct Style {
+    rules: arrange::Rules,
+    format: Format,
+    positions: bool,
+    tables: Tables,
+    footnotes: ast::Placement,
+    authors: bool,
+}
+
+impl Opts {
+    fn style(&self) -> Style {
+        Style {
+            rules: self.rules(),
+            format: self.format,
+            positions: self.positions,
+            tables: self.tables,
+            footnotes: self.footnotes,
+            authors: self.authors,
+        }
+    }
+
+    fn rules(&self) -> arrange::Rules {
+        let mut rules = arrange::Rules::default();
+        for &rule in &self.enable {
+            rules.enable(rule);
+        }
+        for &rule in &self.disable {
+            rules.disable(rule);
+        }
+        rules
+    }
 }
 
 #[derive(StructOpt, Debug)]
@@ -36,10 +136,23 @@ pub enum InputType {
     /// input from StdIO
     #[structopt(name = "-s")]
     StdIO,
+    /// input from the fields of a BibTeX File
+    #[structopt(name = "-b")]
+    Bib {
+        path: PathBuf,
+        /// Fields to convert
+        #[structopt(
+            long,
+            use_delimiter = true,
+            default_value = "title,booktitle,note,abstract"
+        )]
+        fields: Vec<String>,
+    },
 }
 
 pub fn run(opts: Opts) -> i32 {
-    match (run_result(opts.input), opts.output) {
+    let style = opts.style();
+    match (run_result(opts.input, &style), opts.output) {
         (Ok(s), None) => {
             println!("{s}");
             0
@@ -68,24 +181,198 @@ pub fn run(opts: Opts) -> i32 {
     }
 }
 
-fn run_result(opts: InputType) -> Result<String> {
-    let mut raw_code = match opts {
-        InputType::File { path } => read_to_string(&path)
-            .map_err(|err| anyhow::anyhow!("failed to load {}; {}", path.to_string_lossy(), err))?,
-        InputType::Raw { text } => text,
-        InputType::Clipboard => Clipboard::new()?.get_text()?,
+fn run_result(opts: InputType, style: &Style) -> Result<String> {
+    let source = match &opts {
+        InputType::File { path } => Some(path.to_string_lossy().into_owned()),
+        _ => None,
+    };
+    let (mut raw_code, aux_path) = match opts {
+        InputType::Bib { path, fields } => return run_bib(&path, &fields, style),
+        InputType::File { path } => (
+            read_to_string(&path).map_err(|err| {
+                anyhow::anyhow!("failed to load {}; {}", path.to_string_lossy(), err)
+            })?,
+            Some(path.with_extension("aux")),
+        ),
+        InputType::Raw { text } => (text, None),
+        InputType::Clipboard => (Clipboard::new()?.get_text()?, None),
         InputType::StdIO => {
             let mut buffer = String::new();
             std::io::stdin().read_to_string(&mut buffer)?;
-            buffer
+            (buffer, None)
         }
     };
-    preprocess(&mut raw_code);
-    let token = token::Document::from_str(&raw_code)
+    // the .aux file of a compiled document resolves references as in the PDF
+    let resolver = match aux_path.as_ref().map(read_to_string) {
+        Some(Ok(aux)) => ast::Resolver::from_aux(&aux),
+        _ => ast::Resolver::default(),
+    };
+    // `.bib` files are named relative to the document
+    let resolver = match aux_path.as_ref().and_then(|path| path.parent()) {
+        Some(dir) => resolver.in_dir(dir.to_path_buf()),
+        None => resolver,
+    };
+    let preamble = preprocess(&mut raw_code);
+    let start = preamble.as_deref().map(body_start).unwrap_or_default();
+    let preamble = preamble.and_then(|preamble| {
+        token::Document::from_str(&preamble)
+            .map_err(|err| log::warn!("failed to parse the preamble; {err}"))
+            .ok()
+    });
+    let token = token::Document::parse_at(&raw_code, start)
         .map_err(|err| anyhow::anyhow!("failed to parse; {}", err))?;
-    let ast = ast::token_to_ast(token);
+    let mut ast = ast::convert(preamble, token, resolver);
+    if style.tables == Tables::Drop {
+        ast::drop_tables(&mut ast);
+    }
+    if !style.authors {
+        ast::drop_authors(&mut ast);
+    }
+    ast::place_footnotes(&mut ast, style.footnotes);
+    arrange::normalize(&mut ast, &style.rules);
 
-    let mut ast_str = format!("{ast}");
-    arrange::arrange_text_string(&mut ast_str);
+    let mut ast_str = match style.format {
+        Format::Text => format!("{}", ast::Document::from(ast)),
+        Format::Sentences => {
+            let prefix = style.positions.then_some(source.as_deref());
+            ast.sentence_lines(prefix).join("\n\n")
+        }
+    };
+    arrange::tidy(&mut ast_str);
     Ok(ast_str)
 }
+
+/// Converts the `fields` of each entry of a `.bib` file, labelling them with their positions.
+fn run_bib(path: &Path, fields: &[String], style: &Style) -> Result<String> {
+    let raw_code = read_to_string(path)
+        .map_err(|err| anyhow::anyhow!("failed to load {}; {}", path.to_string_lossy(), err))?;
+    Ok(bib_to_text(
+        &raw_code,
+        &path.to_string_lossy(),
+        fields,
+        style,
+    ))
+}
+
+/// Converts the `fields` of each entry of a `.bib` file, headed by their positions in it.
+/// A field which fails to parse is skipped with a warning.
+fn bib_to_text(raw_code: &str, source: &str, fields: &[String], style: &Style) -> String {
+    let db = bib::Database::parse(raw_code);
+    let mut paragraphs = Vec::new();
+    for entry in db.entries() {
+        for field in entry
+            .fields
+            .iter()
+            .filter(|field| fields.contains(&field.name))
+        {
+            let token = match parse_field(raw_code, field) {
+                Ok(token) => token,
+                Err(err) => {
+                    log::warn!("failed to parse {}.{}; {err}", entry.key, field.name);
+                    continue;
+                }
+            };
+            let mut ast = ast::convert(None, token, ast::Resolver::default());
+            arrange::normalize(&mut ast, &style.rules);
+            let mut ast_str = match style.format {
+                Format::Text => format!("{ast}"),
+                Format::Sentences => {
+                    let prefix = style.positions.then_some(Some(source));
+                    ast.sentence_lines(prefix).join("\n\n")
+                }
+            };
+            arrange::tidy(&mut ast_str);
+            let (line, column) = bib::line_column(raw_code, field.offset);
+            paragraphs.push(format!(
+                "% {source}:{line}:{column}: {}.{}\n{}",
+                entry.key,
+                field.name,
+                ast_str.trim()
+            ));
+        }
+    }
+    paragraphs.join("\n\n")
+}
+
+/// Parses the value of a field without the braces protecting its case,
+/// at the positions of its words in the `.bib` file.
+fn parse_field(
+    raw_code: &str,
+    field: &bib::Field,
+) -> Result<token::Document, token::Error<String>> {
+    let (value, removed) = bib::strip_case_braces(&field.value);
+    let (line, column) = bib::line_column(raw_code, field.offset);
+    let mut token = token::Document::parse_at(&value, ast::Position { line, column })?;
+    // a value joined from strings or macros is not in the file as it is
+    if !raw_code[field.offset..].starts_with(&field.value) {
+        return Ok(token);
+    }
+    let removed = removed
+        .into_iter()
+        .map(|i| bib::line_column(raw_code, field.offset + i))
+        .collect::<Vec<_>>();
+    // each brace removed before a word on its line moves the word to the right
+    token.map_positions(&|position| {
+        let mut column = position.column;
+        for &(_, brace) in removed.iter().filter(|(line, _)| *line == position.line) {
+            if brace <= column {
+                column += 1;
+            }
+        }
+        ast::Position { column, ..position }
+    });
+    Ok(token)
+}
+
+#[test]
+fn test_bib_fields() {
+    let raw_code = "@article{knuth,
+  title = {The {BibTeX} {W}ay of $x$},
+  note = {\\begin{itemize}},
+}";
+    let db = bib::Database::parse(raw_code);
+    let title = &db.entries()[0].fields[0];
+    let words = parse_field(raw_code, title)
+        .unwrap()
+        .words
+        .into_iter()
+        .filter_map(|word| match word {
+            token::Word::Text(text, position) => Some((text, position.line, position.column)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let words = words
+        .iter()
+        .map(|(text, line, column)| (text.as_str(), *line, *column))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        words,
+        [
+            ("The", 2, 12),
+            ("BibTeX", 2, 17),
+            ("Way", 2, 26),
+            ("of", 2, 31),
+            ("x", 2, 35)
+        ]
+    );
+    let style = |format, positions| Style {
+        rules: Default::default(),
+        format,
+        positions,
+        tables: Tables::Drop,
+        footnotes: ast::Placement::After,
+        authors: true,
+    };
+    let note = &db.entries()[0].fields[1];
+    assert!(parse_field(raw_code, note).is_err());
+    let fields = ["title".to_string(), "note".to_string()];
+    // the note is skipped
+    assert_eq!(
+        bib_to_text(raw_code, "a.bib", &fields, &style(Format::Text, false)),
+        "% a.bib:2:12: knuth.title\nThe BibTeX Way of XX"
+    );
+    assert_eq!(
+        bib_to_text(raw_code, "a.bib", &fields, &style(Format::Sentences, true)),
+        "% a.bib:2:12: knuth.title\na.bib:2: The BibTeX Way of XX"
+    );
+}
@@ -2,4 +2,5 @@ pub mod preprocess;
 mod token;
 pub mod ast;
 pub mod cli;
-pub mod arrange;
\ No newline at end of file
+pub mod arrange;
+pub mod bib;
\ No newline at end of file
@@ -1,5 +1,4 @@
 use gratex::cli;
-///
 use log::Level;
 use structopt::StructOpt;
 use structopt_flags::LogLevel;
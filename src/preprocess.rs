@@ -1,8 +1,23 @@
-pub fn preprocess(s: &mut String) {
+use crate::ast::Position;
+
+/// Cuts out the body of the document and returns the preamble, if any.
+pub fn preprocess(s: &mut String) -> Option<String> {
     if let Some(start) = s.find("\\begin{document}") {
         if let Some(end) = s.find("\\end{document}") {
+            let preamble = s[..start].to_string();
             *s = s[start + 16..end].to_string();
+            return Some(preamble);
         }
     }
     // println!("{s}")
+    None
+}
+
+/// Where the body cut out by [`preprocess`] starts, after `\begin{document}`.
+pub fn body_start(preamble: &str) -> Position {
+    let line_start = preamble.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: preamble.matches('\n').count() + 1,
+        column: preamble[line_start..].chars().count() + "\\begin{document}".len() + 1,
+    }
 }
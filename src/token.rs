@@ -1,12 +1,16 @@
-use combine::stream::position;
+use combine::stream::position::{self, SourcePosition};
 use std::{fmt, io, str::FromStr};
 
 use self::word::parse_words;
 mod command;
+mod signature;
+mod symbol;
 #[cfg(test)]
 mod test;
 mod word;
-pub use command::Command;
+pub use command::{CiteStyle, Command, RefStyle};
+pub use signature::Class;
+pub use symbol::{ligatures, ESCAPED, NON_FINAL};
 
 #[derive(Debug)]
 pub enum Error<E> {
@@ -26,7 +30,7 @@ where
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct Document {
     pub words: Vec<Word>,
 }
@@ -44,36 +48,94 @@ impl FromStr for Document {
     type Err = Error<String>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        combine::EasyParser::easy_parse(&mut parse_words(), position::Stream::new(s))
+        Document::parse_at(s, Position::default())
+    }
+}
+
+impl Document {
+    /// Parses `s`, which starts at `start` in its source file.
+    pub fn parse_at(s: &str, start: Position) -> Result<Self, Error<String>> {
+        let start = SourcePosition {
+            line: start.line as i32,
+            column: start.column as i32,
+        };
+        let stream = position::Stream::with_positioner(s, start);
+        combine::EasyParser::easy_parse(&mut parse_words(), stream)
             .map_err(|err| Error::Parse(err.to_string()))
             .map(|words| Document { words: words.0 })
     }
+
+    /// Moves the positions of the words, e.g. back into a source changed before parsing.
+    pub fn map_positions(&mut self, f: &impl Fn(Position) -> Position) {
+        for word in &mut self.words {
+            match word {
+                Word::Text(_, position) => *position = f(*position),
+                Word::Lines(doc) | Word::Optional(doc) | Word::Env(_, doc) => doc.map_positions(f),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A position in a source file, counted from 1.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position { line: 1, column: 1 }
+    }
+}
+
+impl From<SourcePosition> for Position {
+    fn from(pos: SourcePosition) -> Self {
+        Position {
+            line: pos.line as usize,
+            column: pos.column as usize,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Comments(String);
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Word {
-    Text(String),
+    /// A word, with the position where it starts.
+    Text(String, Position),
     Command(command::Command),
     Lines(Document),
+    /// An optional argument `[...]`.
+    Optional(Document),
     Comment(Comments),
     Env(String, Document),
     Dollar,
     EndLine,
+    /// The alignment tab `&` between the cells of a table or the columns of display math.
+    Alignment,
 }
 
 impl fmt::Display for Word {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Word::Text(s) => write!(f, "{s} "),
+            Word::Text(s, _) => write!(f, "{s} "),
             Word::Command(c) => write!(f, "{c}"),
             Word::Lines(p) => write!(f, "{p}"),
+            Word::Optional(p) => write!(f, "[{p}]"),
             Word::Comment(s) => write!(f, "%{}", s.0),
             Word::Env(name, d) => write!(f, "\\BEGIN{{{name}}}{d}\\END{{{name}}}"),
-            Word::EndLine => write!(f, "↵\n"),
+            Word::EndLine => writeln!(f, "↵"),
             Word::Dollar => write!(f, "$"),
+            Word::Alignment => write!(f, "&"),
         }
     }
 }
@@ -81,13 +143,15 @@ impl fmt::Display for Word {
 impl Word {
     pub fn is_empty_word(&self) -> bool {
         match self {
-            Word::Text(s) => s.is_empty(),
+            Word::Text(s, _) => s.is_empty(),
             Word::Command(_) => false,
             Word::Lines(_) => false,
+            Word::Optional(_) => false,
             Word::Comment(_) => false,
             Word::Env(_, _) => false,
             Word::EndLine => true,
             Word::Dollar => false,
+            Word::Alignment => false,
         }
     }
 }
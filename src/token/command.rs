@@ -1,18 +1,82 @@
 use std::fmt;
 
+use super::signature::{self, Signature};
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Command {
+    /// A sectioning command by its level: 0 for `\part`, 1 for `\chapter`, 2 for `\section`, ...
     Section(u8),
     Label,
-    Cite,
-    Ref,
+    Cite(CiteStyle),
+    Ref(RefStyle),
+    /// `\crefrange` and `\Crefrange`.
+    RefRange(RefStyle),
     Font,
     Item,
     Space,
+    NewTheorem,
+    NumberWithin,
+    NoNumber,
+    Appendix,
+    UsePackage,
+    /// `\newline`, which is `\\` outside of display math.
+    LineBreak,
+    /// `\enquote` of csquotes.
+    Quote,
+    /// `\bibliography` and `\addbibresource`.
+    Bibliography,
+    DocumentClass,
+    Caption,
+    Footnote,
+    Title,
+    Author,
+    MakeTitle,
+    /// beamer's `\titlepage`, like `\maketitle`. In other classes `titlepage` is an environment.
+    TitlePage,
+    Keywords,
+    /// beamer's `\frametitle`.
+    FrameTitle,
+    /// beamer's `\frame{...}` like the `frame` environment, or LaTeX's framed box.
+    Frame,
+    Known(&'static Signature),
     Unknown(String),
     Symbol(char),
 }
 
+/// How a citation command renders the cited works.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CiteStyle {
+    /// `\cite`: the labels in brackets.
+    Plain,
+    /// `\citep`, `\parencite`, `\autocite`: the labels in brackets.
+    Parenthetical,
+    /// `\citet`, `\textcite`: a noun phrase like `Smith (2020)`.
+    Textual,
+    /// `\citeauthor`: the authors.
+    Author,
+    /// `\citeyear`: the years.
+    Year,
+    /// `\footcite`: a footnote citing the works.
+    Foot,
+}
+
+/// How a reference command renders its target.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RefStyle {
+    /// `\ref`: the number only.
+    Number,
+    /// `\eqref`: the number in parentheses.
+    Equation,
+    /// `\pageref`: the page number.
+    Page,
+    /// `\cref`: the name and the number.
+    Name,
+    /// `\Cref`: the capitalized name and the number.
+    CapitalName,
+    /// `\autoref`: hyperref's name and the number.
+    Auto,
+}
+
 impl Command {
     pub const KEYWORDS: [&str; 15] = [
         "section",
@@ -36,14 +100,30 @@ impl Command {
         "par",
     ];
 
-    pub fn arg_num(&self) -> Option<usize> {
+    /// The argument specification of the command (see [`Signature`]).
+    /// `None` means that all the following brace groups are taken.
+    pub fn arg_spec(&self) -> Option<&'static str> {
         use Command::*;
-        let n = match self {
-            Section(_) | Label | Cite | Ref | Font => 1,
-            Item | Space | Symbol(_) => 0,
+        let spec = match self {
+            Label | Font | Keywords => "m",
+            // `\section*[short title]{title}`
+            Section(_) => "som",
+            // `\caption*[short caption]{caption}`
+            Caption => "som",
+            Ref(_) | Quote => "sm",
+            RefRange(_) => "smm",
+            UsePackage | Bibliography | DocumentClass | Footnote | Title | Author | FrameTitle
+            | Frame => "om",
+            Cite(_) => "soom",
+            NewTheorem => "smomo",
+            NumberWithin => "omm",
+            // `\\*[1ex]`
+            LineBreak | Symbol('\\') => "so",
+            Item | Space | NoNumber | Appendix | MakeTitle | TitlePage | Symbol(_) => "",
+            Known(sig) => sig.spec,
             Unknown(_) => return None,
         };
-        Some(n)
+        Some(spec)
     }
 }
 
@@ -52,16 +132,56 @@ impl std::str::FromStr for Command {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use Command::*;
         let ok = match s {
-            "section" => Section(1),
-            "subsection" => Section(2),
+            "part" => Section(0),
+            "chapter" => Section(1),
+            "section" => Section(2),
+            "subsection" => Section(3),
+            "subsubsection" => Section(4),
+            "paragraph" => Section(5),
+            "subparagraph" => Section(6),
             "label" => Label,
-            "cite" => Cite,
-            "ref" | "cref" | "Cref" => Ref,
+            "cite" | "Cite" => Cite(CiteStyle::Plain),
+            "citep" | "Citep" | "citealp" | "parencite" | "Parencite" | "autocite" | "Autocite" => {
+                Cite(CiteStyle::Parenthetical)
+            }
+            "citet" | "Citet" | "citealt" | "textcite" | "Textcite" => Cite(CiteStyle::Textual),
+            "citeauthor" | "Citeauthor" => Cite(CiteStyle::Author),
+            "citeyear" | "citeyearpar" => Cite(CiteStyle::Year),
+            "footcite" | "footcitetext" => Cite(CiteStyle::Foot),
+            "ref" => Ref(RefStyle::Number),
+            "eqref" => Ref(RefStyle::Equation),
+            "pageref" => Ref(RefStyle::Page),
+            "cref" => Ref(RefStyle::Name),
+            "Cref" => Ref(RefStyle::CapitalName),
+            "autoref" => Ref(RefStyle::Auto),
+            "crefrange" => RefRange(RefStyle::Name),
+            "Crefrange" => RefRange(RefStyle::CapitalName),
+            "usepackage" | "RequirePackage" => UsePackage,
+            "bibliography" | "addbibresource" => Bibliography,
+            "documentclass" => DocumentClass,
+            "caption" => Caption,
+            "footnote" => Footnote,
+            "title" => Title,
+            "author" => Author,
+            "maketitle" => MakeTitle,
+            "titlepage" => TitlePage,
+            "keywords" => Keywords,
+            "frametitle" => FrameTitle,
+            "frame" => Frame,
             "emph" => Font,
-            c if c.strip_prefix("text").is_some() => Font,
             "item" => Item,
             "quad" | "qquad" | "par" => Space,
-            _ => return Err(format!("Compiler BUG: Unknown command name found: {s}")),
+            "newtheorem" => NewTheorem,
+            "numberwithin" => NumberWithin,
+            "nonumber" | "notag" => NoNumber,
+            "appendix" => Appendix,
+            "newline" => LineBreak,
+            "enquote" => Quote,
+            _ => match signature::lookup(s) {
+                Some(sig) => Known(sig),
+                None if s.starts_with("text") => Font,
+                None => return Err(format!("Compiler BUG: Unknown command name found: {s}")),
+            },
         };
         Ok(ok)
     }
@@ -77,11 +197,30 @@ impl fmt::Display for Command {
                 Ok(())
             }
             Command::Label => write!(f, r"\LABEL"),
-            Command::Cite => write!(f, r"\CITE"),
-            Command::Ref => write!(f, r"\REF"),
+            Command::Cite(_) => write!(f, r"\CITE"),
+            Command::Ref(_) | Command::RefRange(_) => write!(f, r"\REF"),
             Command::Font => write!(f, r"\FONT"),
             Command::Item => write!(f, r"\ITEM"),
             Command::Space => write!(f, ""),
+            Command::NewTheorem => write!(f, r"\NEWTHEOREM"),
+            Command::NumberWithin => write!(f, r"\NUMBERWITHIN"),
+            Command::NoNumber => write!(f, r"\NONUMBER"),
+            Command::Appendix => write!(f, r"\APPENDIX"),
+            Command::UsePackage => write!(f, r"\USEPACKAGE"),
+            Command::LineBreak => write!(f, r"\NEWLINE"),
+            Command::Quote => write!(f, r"\ENQUOTE"),
+            Command::Bibliography => write!(f, r"\BIBLIOGRAPHY"),
+            Command::DocumentClass => write!(f, r"\DOCUMENTCLASS"),
+            Command::Caption => write!(f, r"\CAPTION"),
+            Command::Footnote => write!(f, r"\FOOTNOTE"),
+            Command::Title => write!(f, r"\TITLE"),
+            Command::Author => write!(f, r"\AUTHOR"),
+            Command::MakeTitle => write!(f, r"\MAKETITLE"),
+            Command::TitlePage => write!(f, r"\TITLEPAGE"),
+            Command::Keywords => write!(f, r"\KEYWORDS"),
+            Command::FrameTitle => write!(f, r"\FRAMETITLE"),
+            Command::Frame => write!(f, r"\FRAME"),
+            Command::Known(sig) => write!(f, r"\{}", sig.name),
             Command::Unknown(s) => write!(f, r"\{}", s.to_uppercase()),
             Command::Symbol(c) => write!(f, r"\{c}"),
         }
@@ -0,0 +1,232 @@
+/// What a known command means for the readable text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Class {
+    /// Spacing, page and counter layout. Renders nothing.
+    Layout,
+    /// Hyperlinks. Renders the link text, or the target itself when there is no text.
+    Reference,
+    /// Font, color and box commands. Renders the last mandatory argument.
+    Font,
+    /// Commands whose arguments are not prose (files, packages, definitions). Renders nothing.
+    Drop,
+}
+
+/// The argument signature of a command, in the spirit of `xparse`:
+/// `s` is an optional star, `o` an optional `[...]` argument
+/// and `m` a mandatory `{...}` argument.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub name: &'static str,
+    pub spec: &'static str,
+    pub class: Class,
+}
+
+impl Signature {
+    const fn new(name: &'static str, spec: &'static str, class: Class) -> Self {
+        Signature { name, spec, class }
+    }
+}
+
+pub fn lookup(name: &str) -> Option<&'static Signature> {
+    SIGNATURES.iter().find(|sig| sig.name == name)
+}
+
+use Class::*;
+
+const SIGNATURES: &[Signature] = &[
+    // LaTeX kernel: spacing and page layout
+    Signature::new("vspace", "sm", Layout),
+    Signature::new("hspace", "sm", Layout),
+    Signature::new("addvspace", "m", Layout),
+    Signature::new("vfill", "", Layout),
+    Signature::new("hfill", "", Layout),
+    Signature::new("smallskip", "", Layout),
+    Signature::new("medskip", "", Layout),
+    Signature::new("bigskip", "", Layout),
+    Signature::new("newpage", "", Layout),
+    Signature::new("clearpage", "", Layout),
+    Signature::new("cleardoublepage", "", Layout),
+    Signature::new("pagebreak", "o", Layout),
+    Signature::new("nopagebreak", "o", Layout),
+    Signature::new("linebreak", "o", Layout),
+    Signature::new("nolinebreak", "o", Layout),
+    Signature::new("enlargethispage", "sm", Layout),
+    Signature::new("noindent", "", Layout),
+    Signature::new("indent", "", Layout),
+    Signature::new("centering", "", Layout),
+    Signature::new("raggedright", "", Layout),
+    Signature::new("raggedleft", "", Layout),
+    Signature::new("onecolumn", "", Layout),
+    Signature::new("twocolumn", "o", Layout),
+    Signature::new("setlength", "mm", Layout),
+    Signature::new("addtolength", "mm", Layout),
+    Signature::new("setcounter", "mm", Layout),
+    Signature::new("addtocounter", "mm", Layout),
+    Signature::new("stepcounter", "m", Layout),
+    Signature::new("refstepcounter", "m", Layout),
+    Signature::new("textwidth", "", Layout),
+    Signature::new("textheight", "", Layout),
+    Signature::new("linewidth", "", Layout),
+    Signature::new("columnwidth", "", Layout),
+    Signature::new("paperwidth", "", Layout),
+    Signature::new("paperheight", "", Layout),
+    Signature::new("baselineskip", "", Layout),
+    Signature::new("parindent", "", Layout),
+    Signature::new("parskip", "", Layout),
+    Signature::new("tabcolsep", "", Layout),
+    Signature::new("arraystretch", "", Layout),
+    // LaTeX kernel: fonts and boxes
+    Signature::new("underline", "m", Font),
+    Signature::new("mbox", "m", Font),
+    Signature::new("fbox", "m", Font),
+    Signature::new("makebox", "oom", Font),
+    Signature::new("framebox", "oom", Font),
+    Signature::new("parbox", "ooomm", Font),
+    Signature::new("raisebox", "moom", Font),
+    Signature::new("bfseries", "", Font),
+    Signature::new("mdseries", "", Font),
+    Signature::new("itshape", "", Font),
+    Signature::new("slshape", "", Font),
+    Signature::new("scshape", "", Font),
+    Signature::new("upshape", "", Font),
+    Signature::new("rmfamily", "", Font),
+    Signature::new("sffamily", "", Font),
+    Signature::new("ttfamily", "", Font),
+    Signature::new("normalfont", "", Font),
+    Signature::new("em", "", Font),
+    Signature::new("bf", "", Font),
+    Signature::new("it", "", Font),
+    Signature::new("rm", "", Font),
+    Signature::new("sf", "", Font),
+    Signature::new("tt", "", Font),
+    Signature::new("sc", "", Font),
+    Signature::new("tiny", "", Font),
+    Signature::new("scriptsize", "", Font),
+    Signature::new("footnotesize", "", Font),
+    Signature::new("small", "", Font),
+    Signature::new("normalsize", "", Font),
+    Signature::new("large", "", Font),
+    Signature::new("Large", "", Font),
+    Signature::new("LARGE", "", Font),
+    Signature::new("huge", "", Font),
+    Signature::new("Huge", "", Font),
+    // LaTeX kernel: preamble, definitions and invisible marks
+    Signature::new("newcommand", "smoom", Drop),
+    Signature::new("renewcommand", "smoom", Drop),
+    Signature::new("providecommand", "smoom", Drop),
+    Signature::new("newenvironment", "smoomm", Drop),
+    Signature::new("renewenvironment", "smoomm", Drop),
+    Signature::new("newcounter", "mo", Drop),
+    Signature::new("input", "m", Drop),
+    Signature::new("include", "m", Drop),
+    Signature::new("includeonly", "m", Drop),
+    Signature::new("pagestyle", "m", Drop),
+    Signature::new("thispagestyle", "m", Drop),
+    Signature::new("pagenumbering", "m", Drop),
+    Signature::new("index", "m", Drop),
+    Signature::new("glossary", "m", Drop),
+    Signature::new("nocite", "m", Drop),
+    Signature::new("bibliographystyle", "m", Drop),
+    Signature::new("tableofcontents", "", Drop),
+    Signature::new("listoffigures", "", Drop),
+    Signature::new("listoftables", "", Drop),
+    Signature::new("addcontentsline", "mmm", Drop),
+    Signature::new("addtocontents", "mm", Drop),
+    Signature::new("markboth", "mm", Drop),
+    Signature::new("markright", "m", Drop),
+    Signature::new("phantom", "m", Drop),
+    Signature::new("hphantom", "m", Drop),
+    Signature::new("vphantom", "m", Drop),
+    Signature::new("protect", "", Drop),
+    Signature::new("thanks", "m", Drop),
+    Signature::new("date", "m", Drop),
+    Signature::new("ensuremath", "m", Drop),
+    // graphicx
+    Signature::new("includegraphics", "som", Drop),
+    Signature::new("graphicspath", "m", Drop),
+    Signature::new("scalebox", "mom", Font),
+    Signature::new("rotatebox", "omm", Font),
+    Signature::new("resizebox", "smm", Font),
+    Signature::new("reflectbox", "m", Font),
+    // hyperref
+    Signature::new("url", "m", Reference),
+    Signature::new("nolinkurl", "m", Reference),
+    Signature::new("href", "omm", Reference),
+    Signature::new("hyperref", "om", Reference),
+    Signature::new("hyperlink", "mm", Reference),
+    Signature::new("hypertarget", "mm", Reference),
+    Signature::new("texorpdfstring", "mm", Font),
+    Signature::new("hypersetup", "m", Drop),
+    Signature::new("phantomsection", "", Drop),
+    // amsmath
+    Signature::new("intertext", "m", Font),
+    Signature::new("tag", "sm", Drop),
+    Signature::new("DeclareMathOperator", "smm", Drop),
+    Signature::new("theoremstyle", "m", Drop),
+    // geometry
+    Signature::new("geometry", "m", Layout),
+    Signature::new("newgeometry", "m", Layout),
+    Signature::new("restoregeometry", "", Layout),
+    Signature::new("savegeometry", "m", Layout),
+    Signature::new("loadgeometry", "m", Layout),
+    // xcolor
+    Signature::new("textcolor", "omm", Font),
+    Signature::new("colorbox", "omm", Font),
+    Signature::new("fcolorbox", "ommm", Font),
+    Signature::new("color", "om", Drop),
+    Signature::new("pagecolor", "om", Drop),
+    Signature::new("definecolor", "omm", Drop),
+    Signature::new("colorlet", "omom", Drop),
+    // tables
+    Signature::new("hline", "", Layout),
+    Signature::new("cline", "m", Layout),
+    Signature::new("hhline", "m", Layout),
+    Signature::new("multicolumn", "mmm", Font),
+    Signature::new("multirow", "omomom", Font),
+    Signature::new("endhead", "", Layout),
+    Signature::new("endfirsthead", "", Layout),
+    Signature::new("endfoot", "", Layout),
+    Signature::new("endlastfoot", "", Layout),
+    // booktabs
+    Signature::new("toprule", "o", Layout),
+    Signature::new("midrule", "o", Layout),
+    Signature::new("bottomrule", "o", Layout),
+    Signature::new("cmidrule", "om", Layout),
+    Signature::new("addlinespace", "o", Layout),
+    Signature::new("specialrule", "mmm", Layout),
+    // tikz
+    Signature::new("usetikzlibrary", "m", Drop),
+    Signature::new("tikzset", "m", Drop),
+    Signature::new("pgfplotsset", "m", Drop),
+    Signature::new("tikz", "om", Drop),
+    Signature::new("draw", "o", Drop),
+    Signature::new("fill", "o", Drop),
+    Signature::new("filldraw", "o", Drop),
+    Signature::new("path", "o", Drop),
+    Signature::new("node", "o", Drop),
+    Signature::new("coordinate", "o", Drop),
+    // beamer, whose overlay specifications are dropped by the parser
+    Signature::new("pause", "o", Layout),
+    Signature::new("onslide", "", Layout),
+    Signature::new("only", "m", Font),
+    Signature::new("uncover", "m", Font),
+    Signature::new("visible", "m", Font),
+    Signature::new("invisible", "m", Font),
+    Signature::new("alert", "m", Font),
+    Signature::new("structure", "m", Font),
+    Signature::new("framesubtitle", "m", Font),
+    Signature::new("usetheme", "om", Drop),
+    Signature::new("usecolortheme", "om", Drop),
+    Signature::new("usefonttheme", "om", Drop),
+    Signature::new("useinnertheme", "om", Drop),
+    Signature::new("useoutertheme", "om", Drop),
+    Signature::new("setbeamertemplate", "mo", Drop),
+    Signature::new("setbeamercolor", "mm", Drop),
+    Signature::new("setbeamerfont", "mm", Drop),
+    Signature::new("setbeamercovered", "m", Drop),
+    Signature::new("institute", "om", Drop),
+    Signature::new("subtitle", "om", Drop),
+    Signature::new("logo", "m", Drop),
+    Signature::new("AtBeginSection", "om", Drop),
+    Signature::new("AtBeginSubsection", "om", Drop),
+];
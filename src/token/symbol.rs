@@ -0,0 +1,168 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters which a backslash escapes to print them literally, e.g. `\%`.
+pub const ESCAPED: [char; 7] = ['%', '&', '$', '#', '_', '{', '}'];
+
+/// Marks a period which does not end a sentence, written `\@.`, `.\@` or `.\ ` in the source.
+/// It is invisible in the output.
+pub const NON_FINAL: char = '\u{2060}';
+
+/// Ligatures of TeX fonts, longest first. A tie `~` is a space which keeps words together.
+/// Quotes are kept as they are written, for the normalization to choose their style.
+const LIGATURES: [(&str, &str); 5] = [
+    ("---", "—"),
+    ("--", "–"),
+    ("!`", "¡"),
+    ("?`", "¿"),
+    ("~", " "),
+];
+
+/// Text symbol commands and the text they print.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("ldots", "…"),
+    ("dots", "…"),
+    ("textellipsis", "…"),
+    ("textendash", "–"),
+    ("textemdash", "—"),
+    ("S", "§"),
+    ("P", "¶"),
+    ("textsection", "§"),
+    ("textparagraph", "¶"),
+    ("dag", "†"),
+    ("ddag", "‡"),
+    ("textdagger", "†"),
+    ("textdaggerdbl", "‡"),
+    ("copyright", "©"),
+    ("textcopyright", "©"),
+    ("textregistered", "®"),
+    ("texttrademark", "™"),
+    ("pounds", "£"),
+    ("textsterling", "£"),
+    ("texteuro", "€"),
+    ("textdegree", "°"),
+    ("textbullet", "•"),
+    ("textperiodcentered", "·"),
+    ("textasciitilde", "~"),
+    ("textasciicircum", "^"),
+    ("textbackslash", "\\"),
+    ("textbar", "|"),
+    ("textless", "<"),
+    ("textgreater", ">"),
+    ("textunderscore", "_"),
+    ("textquoteleft", "‘"),
+    ("textquoteright", "’"),
+    ("textquotedblleft", "“"),
+    ("textquotedblright", "”"),
+    ("textexclamdown", "¡"),
+    ("textquestiondown", "¿"),
+    ("TeX", "TeX"),
+    ("LaTeX", "LaTeX"),
+    ("LaTeXe", "LaTeX2e"),
+];
+
+/// The text printed by a text symbol command such as `ldots`.
+pub fn lookup(name: &str) -> Option<&'static str> {
+    SYMBOLS
+        .iter()
+        .find(|(symbol, _)| *symbol == name)
+        .map(|(_, text)| *text)
+}
+
+/// Accent commands and the combining characters they put on a letter, e.g. `\'e` for `é`.
+const ACCENTS: &[(&str, char)] = &[
+    ("'", '\u{301}'),
+    ("`", '\u{300}'),
+    ("^", '\u{302}'),
+    ("\"", '\u{308}'),
+    ("~", '\u{303}'),
+    ("=", '\u{304}'),
+    (".", '\u{307}'),
+    ("u", '\u{306}'),
+    ("v", '\u{30c}'),
+    ("H", '\u{30b}'),
+    ("r", '\u{30a}'),
+    ("c", '\u{327}'),
+    ("k", '\u{328}'),
+    ("d", '\u{323}'),
+    ("b", '\u{331}'),
+    // the tie over two letters, as in `\t{oo}`
+    ("t", '\u{361}'),
+];
+
+/// Commands printing special letters, e.g. `\ss` for `ß`.
+const LETTERS: &[(&str, &str)] = &[
+    ("ss", "ß"),
+    ("SS", "SS"),
+    ("ae", "æ"),
+    ("AE", "Æ"),
+    ("oe", "œ"),
+    ("OE", "Œ"),
+    ("aa", "å"),
+    ("AA", "Å"),
+    ("o", "ø"),
+    ("O", "Ø"),
+    ("l", "ł"),
+    ("L", "Ł"),
+    ("i", "ı"),
+    ("j", "ȷ"),
+    ("dh", "ð"),
+    ("DH", "Ð"),
+    ("th", "þ"),
+    ("TH", "Þ"),
+    ("ng", "ŋ"),
+    ("NG", "Ŋ"),
+];
+
+/// The combining character of an accent command such as `'` or `v`.
+pub fn accent(name: &str) -> Option<char> {
+    ACCENTS
+        .iter()
+        .find(|(accent, _)| *accent == name)
+        .map(|(_, mark)| *mark)
+}
+
+/// The letter printed by a command such as `ss`.
+pub fn letter(name: &str) -> Option<&'static str> {
+    LETTERS
+        .iter()
+        .find(|(letter, _)| *letter == name)
+        .map(|(_, text)| *text)
+}
+
+/// Puts an accent on the first letter of `base`. The dotless `\i` and `\j` get their dots back,
+/// since Unicode composes accented `i` and `j` from the dotted ones.
+pub fn accented(base: &str, mark: char) -> String {
+    let mut chars = base.chars();
+    let first = match chars.next() {
+        Some('ı') => 'i',
+        Some('ȷ') => 'j',
+        Some(first) => first,
+        None => return mark.to_string(),
+    };
+    [first, mark].into_iter().chain(chars).nfc().collect()
+}
+
+/// Replaces the ligatures of a text such as `1--3` or `Fig.~3`.
+pub fn ligatures(s: &str) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(ch) = rest.chars().next() {
+        match LIGATURES.iter().find(|(from, _)| rest.starts_with(from)) {
+            Some((from, to)) => {
+                out += to;
+                rest = &rest[from.len()..];
+            }
+            None => {
+                out.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn test_ligatures() {
+    assert_eq!(ligatures("1--3 --- ``don't''"), "1–3 — ``don't''");
+    assert_eq!(ligatures("!`Hola! Fig.~3"), "¡Hola! Fig. 3");
+}
@@ -1,5 +1,13 @@
 use super::*;
 
+/// The text of a word, or nothing for other words.
+fn text(word: &Word) -> &str {
+    match word {
+        Word::Text(s, _) => s,
+        _ => "",
+    }
+}
+
 #[test]
 fn test_paragraph_from_str() {
     let s = 
@@ -33,7 +41,11 @@ fn test_debug() {
     dbg!(&p);
     assert!(p.is_ok());
     match p {
-        Ok(p) => println!("{p}"),
+        Ok(p) => {
+            println!("{p}");
+            let period = format!("{NON_FINAL}.");
+            assert_eq!(text(&p.words[6]), format!("Ph{period}"));
+        }
         Err(_) => unreachable!(),
     }
 }
@@ -53,6 +65,52 @@ fn test_debug2() {
     }
 }
 
+#[test]
+fn test_optional() {
+    let s = r"\includegraphics[width=0.5\textwidth]{fig.pdf} [0,1) $[0,1)$";
+    let p = Document::from_str(s).unwrap();
+    assert!(matches!(p.words[1], Word::Optional(_)));
+    assert_eq!(text(&p.words[3]), "[");
+    assert_eq!(text(&p.words[4]), "0,1)");
+    assert_eq!(p.words[5], Word::Dollar);
+    let Word::Text(_, position) = p.words[4] else {
+        unreachable!()
+    };
+    assert_eq!(position, Position { line: 1, column: 49 });
+}
+
+#[test]
+fn test_symbols() {
+    let s = r"50\% of \$5 \S\,3 and so on\ldots{} \textendash";
+    let p = Document::from_str(s).unwrap();
+    assert_eq!(text(&p.words[0]), "50%");
+    assert_eq!(text(&p.words[2]), "$5");
+    assert_eq!(text(&p.words[3]), "§");
+    assert_eq!(text(&p.words[8]), "on…");
+    assert_eq!(text(&p.words[9]), "–");
+}
+
+#[test]
+fn test_spacing() {
+    let s = r"pages 1--3 --- Fig.~3 e.g.\ this";
+    let p = Document::from_str(s).unwrap();
+    let words = ["pages", "1--3", "---", "Fig.~3", &format!("e.g.{NON_FINAL} this")];
+    assert_eq!(p.words.iter().map(text).collect::<Vec<_>>(), words);
+}
+
+#[test]
+fn test_accents() {
+    let s = r#"Erd{\H{o}}s G\"odel {\'E}tienne Fran\c{c}ois na\"{\i}ve Stra\ss e \v{S}koda"#;
+    let p = Document::from_str(s).unwrap();
+    let words = ["Erdős", "Gödel", "Étienne", "François", "naïve", "Straße", "Škoda"];
+    assert_eq!(p.words.iter().map(text).collect::<Vec<_>>(), words);
+    // `\d` without a group may be the differential of the document
+    let p = Document::from_str(r"write \d x and \'e").unwrap();
+    assert_eq!(p.words[1], Word::Command(Command::Unknown("d".to_string())));
+    assert_eq!(text(&p.words[2]), "x");
+    assert_eq!(text(&p.words[4]), "é");
+}
+
 // #[test]
 // fn test_text() {
 //     let result = parse_text().parse("abc xyz");
@@ -86,3 +144,31 @@ fn test_debug2() {
 //     let subst = make_upper_substitute(s);
 //     assert_eq!(subst, "AXX".to_string());
 // }
+
+#[test]
+fn test_overlays() {
+    // overlay specifications are text, which the conversion drops in beamer documents
+    let s = r"\item<2-> a $\epsilon<1, x>0$";
+    let p = Document::from_str(s).unwrap();
+    assert_eq!(p.words[0], Word::Command(Command::Item));
+    assert_eq!(text(&p.words[1]), "<2->");
+    assert_eq!(text(&p.words[2]), "a");
+    assert_eq!(text(&p.words[5]), "<1,");
+    assert_eq!(text(&p.words[6]), "x>0");
+}
+
+#[test]
+fn test_math_in_optional() {
+    let s = r"\item[$[a,b]$-th] x";
+    let p = Document::from_str(s).unwrap();
+    let Word::Optional(term) = &p.words[1] else {
+        unreachable!()
+    };
+    let Word::Lines(math) = &term.words[0] else {
+        unreachable!()
+    };
+    assert_eq!(math.words.first(), Some(&Word::Dollar));
+    assert_eq!(math.words.last(), Some(&Word::Dollar));
+    assert_eq!(text(&term.words[1]), "-th");
+    assert_eq!(text(&p.words[2]), "x");
+}
@@ -1,42 +1,91 @@
 // use super::command::parse_command;
-use super::{command::Command, *};
+use super::{command::Command, symbol, *};
+use combine::stream::position::SourcePosition;
+use unicode_normalization::UnicodeNormalization;
 use combine::{
-    attempt, between, choice, many, many1, none_of, parser,
+    attempt, between, choice, look_ahead, many, many1, none_of, not_followed_by, one_of, parser,
     parser::char::{letter, string},
-    satisfy, sep_end_by, token, unexpected_any, value, ParseError, Parser, Stream,
+    position, satisfy, sep_end_by, token, unexpected_any, value, ParseError, Parser, Stream,
 };
 
 parser! {
     pub fn parse_words[Input]()(Input) -> Vec<Word>
     where [
-        Input: Stream<Token = char>,
+        Input: Stream<Token = char, Position = SourcePosition>,
         Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
     ]{
         parse_pure_spaces()
-            .with(sep_end_by(parse_word(), parse_pure_spaces()))
+            .with(sep_end_by(parse_word(false), parse_pure_spaces()))
     }
 }
 
-pub fn parse_word<Input>() -> impl Parser<Input, Output = Word>
+parser! {
+    fn parse_bracket_words[Input]()(Input) -> Vec<Word>
+    where [
+        Input: Stream<Token = char, Position = SourcePosition>,
+        Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+    ]{
+        parse_pure_spaces()
+            .with(sep_end_by(parse_word(true), parse_pure_spaces()))
+    }
+}
+
+parser! {
+    /// Inline math in an optional argument, where a `]` is part of the math.
+    fn parse_bracket_math[Input]()(Input) -> Vec<Word>
+    where [
+        Input: Stream<Token = char, Position = SourcePosition>,
+        Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+    ]{
+        let word = not_followed_by(token('$')).with(parse_word(false));
+        let words = parse_pure_spaces().with(sep_end_by(word, parse_pure_spaces()));
+        between(token('$'), token('$'), words)
+            .map(|words: Vec<Word>| {
+                let mut math = vec![Word::Dollar];
+                math.extend(words);
+                math.push(Word::Dollar);
+                math
+            })
+    }
+}
+
+/// `in_bracket` is set inside an optional argument, where `]` ends the text
+/// except in inline math.
+pub fn parse_word<Input>(in_bracket: bool) -> impl Parser<Input, Output = Word>
 where
-    Input: Stream<Token = char>,
+    Input: Stream<Token = char, Position = SourcePosition>,
     Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
 {
     parse_pure_spaces().with(choice((
         parse_env(),
+        parse_text(in_bracket),
         between(token('{'), token('}'), parse_words()).map(|words| Word::Lines(Document { words })),
+        parse_optional(),
         parse_math_display(),
-        parse_math_inline(),
+        parse_math_inline(in_bracket),
         parse_comments().map(Word::Comment),
         parse_command().map(Word::Command),
-        parse_text(),
         parse_endl(),
+        token('&').map(|_| Word::Alignment),
+        // an unbalanced `[` is just text
+        (position(), token('[')).map(|(pos, ch): (SourcePosition, char)| {
+            Word::Text(ch.to_string(), pos.into())
+        }),
     )))
 }
 
+pub fn parse_optional<Input>() -> impl Parser<Input, Output = Word>
+where
+    Input: Stream<Token = char, Position = SourcePosition>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    attempt(between(token('['), token(']'), parse_bracket_words()))
+        .map(|words| Word::Optional(Document { words }))
+}
+
 pub fn parse_env<Input>() -> impl Parser<Input, Output = Word>
 where
-    Input: Stream<Token = char>,
+    Input: Stream<Token = char, Position = SourcePosition>,
     Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
 {
     (
@@ -60,7 +109,7 @@ where
 
 pub fn parse_math_display<Input>() -> impl Parser<Input, Output = Word>
 where
-    Input: Stream<Token = char>,
+    Input: Stream<Token = char, Position = SourcePosition>,
     Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
 {
     between(
@@ -68,20 +117,26 @@ where
         attempt(string("\\]")),
         parse_words(),
     )
-    .map(|words: Vec<Word>| Word::Env("equation".to_string(), Document { words }))
+    .map(|words: Vec<Word>| Word::Env("equation*".to_string(), Document { words }))
 }
 
-pub fn parse_math_inline<Input>() -> impl Parser<Input, Output = Word>
+pub fn parse_math_inline<Input>(in_bracket: bool) -> impl Parser<Input, Output = Word>
 where
-    Input: Stream<Token = char>,
+    Input: Stream<Token = char, Position = SourcePosition>,
     Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
 {
-    token('$').map(|_| Word::Dollar)
+    match in_bracket {
+        false => token('$').map(|_| Word::Dollar).left(),
+        // the whole math as a group, whose `]` does not end the optional argument
+        true => parse_bracket_math()
+            .map(|words| Word::Lines(Document { words }))
+            .right(),
+    }
 }
 
 fn parse_comments<Input>() -> impl Parser<Input, Output = Comments>
 where
-    Input: Stream<Token = char>,
+    Input: Stream<Token = char, Position = SourcePosition>,
     Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
 {
     token('%')
@@ -91,7 +146,7 @@ where
 
 fn parse_command<Input>() -> impl Parser<Input, Output = Command>
 where
-    Input: Stream<Token = char>,
+    Input: Stream<Token = char, Position = SourcePosition>,
     Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
 {
     attempt(token('\\').with(choice((
@@ -107,20 +162,136 @@ where
     ))))
 }
 
-fn parse_text<Input>() -> impl Parser<Input, Output = Word>
+fn parse_text<Input>(in_bracket: bool) -> impl Parser<Input, Output = Word>
 where
-    Input: Stream<Token = char>,
+    Input: Stream<Token = char, Position = SourcePosition>,
     Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
 {
-    choice((many1(none_of(
-        ['$', '\t', '\n', ' ', '{', '}', '%', '\\'].iter().cloned(),
+    // an empty group ends a command inside a word, as in `\ldots{}`
+    let empty = attempt(string("{}")).map(|_| String::new());
+    // a group starting a word is taken as an argument unless letters follow it
+    let first = choice((
+        parse_text_piece(in_bracket),
+        attempt(parse_letter_group().skip(look_ahead(satisfy(char::is_alphabetic)))),
+    ));
+    let rest = choice((parse_text_piece(in_bracket), parse_letter_group(), empty));
+    (position(), first, many(rest)).map(|(pos, first, rest): (SourcePosition, String, String)| {
+        Word::Text((first + &rest).nfc().collect(), pos.into())
+    })
+}
+
+/// Accented and special letters in braces such as `{\'E}tienne` or `Erd{\H{o}}s`.
+fn parse_letter_group<Input>() -> impl Parser<Input, Output = String>
+where
+    Input: Stream<Token = char, Position = SourcePosition>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    attempt(between(
+        token('{'),
+        token('}'),
+        many1(choice((parse_accent(), parse_letter()))),
     ))
-    .map(Word::Text),))
+}
+
+/// A character or a command printing text, which does not break a word.
+fn parse_text_piece<Input>(in_bracket: bool) -> impl Parser<Input, Output = String>
+where
+    Input: Stream<Token = char, Position = SourcePosition>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let special: &[char] = if in_bracket {
+        &['$', '\t', '\n', ' ', '{', '}', '%', '\\', '&', '[', ']']
+    } else {
+        &['$', '\t', '\n', ' ', '{', '}', '%', '\\', '&', '[']
+    };
+    let escaped = attempt(token('\\').with(one_of(symbol::ESCAPED))).map(String::from);
+    // a text symbol such as `\ldots`, which stays in the word it ends
+    let text_symbol = attempt(token('\\').with(many1(letter())).then(|name: String| {
+        match symbol::lookup(&name) {
+            Some(text) => value(text.to_string()).left(),
+            None => unexpected_any("command").right(),
+        }
+    }));
+    let ch = none_of(special.iter().cloned()).map(String::from);
+    choice((
+        parse_spacing(),
+        ch,
+        escaped,
+        parse_accent(),
+        parse_letter(),
+        text_symbol,
+    ))
+}
+
+/// Spacing commands which do not break a word.
+fn parse_spacing<Input>() -> impl Parser<Input, Output = String>
+where
+    Input: Stream<Token = char, Position = SourcePosition>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    choice((
+        attempt(string("\\@")).map(|_| symbol::NON_FINAL.to_string()),
+        // an interword space after a period, as in `etc.\ and`
+        attempt(string("\\ ")).map(|_| format!("{} ", symbol::NON_FINAL)),
+        // the italic correction
+        attempt(string("\\/")).map(|_| String::new()),
+    ))
+}
+
+/// An accented letter such as `\'e`, `\"{o}`, `\c{c}` or `\'{\i}`.
+/// An accent named by a letter such as `\d` takes a group only, since documents
+/// often define such a command for something else, e.g. `\d x` for the differential.
+fn parse_accent<Input>() -> impl Parser<Input, Output = String>
+where
+    Input: Stream<Token = char, Position = SourcePosition>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let name = token('\\').with(choice((
+        one_of(['\'', '`', '^', '"', '~', '=', '.']).map(String::from),
+        many1(letter()),
+    )));
+    attempt(name.then(|name: String| match symbol::accent(&name) {
+        Some(mark) => {
+            let group = || between(token('{'), token('}'), many(parse_base()));
+            let base = match name.starts_with(char::is_alphabetic) {
+                true => group().left(),
+                false => choice((group(), parse_pure_spaces().with(parse_base()))).right(),
+            };
+            base.map(move |base: String| symbol::accented(&base, mark))
+                .left()
+        }
+        None => unexpected_any("command").right(),
+    }))
+}
+
+/// The letter under an accent.
+fn parse_base<Input>() -> impl Parser<Input, Output = String>
+where
+    Input: Stream<Token = char, Position = SourcePosition>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    choice((
+        satisfy(|ch: char| ch.is_alphanumeric()).map(String::from),
+        parse_letter(),
+    ))
+}
+
+/// A special letter such as `\ss`, which takes the spaces after it like any control word.
+fn parse_letter<Input>() -> impl Parser<Input, Output = String>
+where
+    Input: Stream<Token = char, Position = SourcePosition>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let name = token('\\').with(many1(letter()));
+    attempt(name.then(|name: String| match symbol::letter(&name) {
+        Some(text) => parse_pure_spaces().map(move |_| text.to_string()).left(),
+        None => unexpected_any("command").right(),
+    }))
 }
 
 pub fn parse_pure_spaces<Input>() -> impl Parser<Input, Output = ()>
 where
-    Input: Stream<Token = char>,
+    Input: Stream<Token = char, Position = SourcePosition>,
     Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
 {
     many(satisfy(|c: char| c != '\n' && c.is_whitespace())).map(|_: String| ())
@@ -128,7 +299,7 @@ where
 
 pub fn parse_endl<Input>() -> impl Parser<Input, Output = Word>
 where
-    Input: Stream<Token = char>,
+    Input: Stream<Token = char, Position = SourcePosition>,
     Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
 {
     token('\n').map(|_| Word::EndLine)